@@ -6,28 +6,95 @@
 //  - операторы (Stmt)
 //  - выражения (Expr)
 //  - двоичные операции (BinOp)
+//  - унарные операции (UnOp)
 //  - функции и программа целиком (Function, Program)
+//  - позиции в исходном тексте (Span) — для сообщений об ошибках
+
+use std::rc::Rc;
 
 /// Статические типы языка.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     /// Целое число
     Int,
+    /// Число с плавающей точкой (`f64`)
+    Float,
     /// Логическое значение
     Bool,
     /// Строка
     Str,
-    /// Список значений (пока без параметризации по типу элементов)
-    List,
+    /// Один символ (Unicode scalar value)
+    Char,
+    /// Список значений: `list[int]`, `list[str]`, ...
+    /// `None` — просто `list`, т.е. список чего угодно.
+    List(Option<Box<Type>>),
+    /// Словарь `{key: value}` (типы ключей и значений не проверяются).
+    Dict,
+    /// Кортеж `(a, b, ...)` (типы элементов не проверяются).
+    Tuple,
+    /// Функция как значение (лямбда или имя функции); сигнатура не проверяется.
+    Func,
+}
+
+impl std::fmt::Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Int => write!(f, "int"),
+            Type::Float => write!(f, "float"),
+            Type::Bool => write!(f, "bool"),
+            Type::Str => write!(f, "str"),
+            Type::Char => write!(f, "char"),
+            Type::List(None) => write!(f, "list"),
+            Type::List(Some(elem)) => write!(f, "list[{}]", elem),
+            Type::Dict => write!(f, "dict"),
+            Type::Tuple => write!(f, "tuple"),
+            Type::Func => write!(f, "func"),
+        }
+    }
+}
+
+/// Позиция узла в исходном тексте: строка, с которой он начинается (с 1).
+/// В AST она необязательна (`Option<Span>`): узлы, которые строит
+/// не парсер (оптимизатор, тесты), могут обойтись без неё.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub line: usize,
+}
+
+impl std::fmt::Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}", self.line)
+    }
 }
 
 /// Оператор (statement).
 /// Это всё, что выполняется "как действие": объявления, присваивания, if, циклы, return и т.п.
 #[derive(Debug, Clone)]
+#[allow(clippy::enum_variant_names)]
 pub enum Stmt {
     /// Объявление переменной:
-    ///   var name: ty = init
-    VarDecl { name: String, ty: Type, init: Expr },
+    ///   var name: ty = init       // неизменяемая
+    ///   var mut name: ty = init   // можно переприсваивать
+    ///   var name = init           // тип выводится из значения
+    VarDecl {
+        name: String,
+        mutable: bool,
+        ty: Option<Type>,
+        init: Expr,
+        span: Option<Span>,
+    },
+
+    /// Деструктурирующее объявление:
+    ///   var a, b = pair
+    ///   var mut first, rest = pop(xs)
+    /// Справа — список или кортеж ровно из `names.len()` элементов.
+    /// Типы переменных выводятся из значений.
+    VarDestructure {
+        names: Vec<String>,
+        mutable: bool,
+        init: Expr,
+        span: Option<Span>,
+    },
 
     /// Оператор-выражение:
     ///   <expr>
@@ -36,7 +103,19 @@ pub enum Stmt {
 
     /// Присваивание:
     ///   name = expr
-    Assign { name: String, expr: Expr },
+    Assign {
+        name: String,
+        expr: Expr,
+        span: Option<Span>,
+    },
+
+    /// Деструктурирующее присваивание:
+    ///   a, b = b, a        // справа — список или кортеж
+    AssignDestructure {
+        names: Vec<String>,
+        expr: Expr,
+        span: Option<Span>,
+    },
 
     /// Ветвление if / elif* / else:
     ///
@@ -59,8 +138,25 @@ pub enum Stmt {
     /// Одна ветка вида `elif cond { then_branch... }`.
     ElseIfBranch { cond: Expr, then_branch: Vec<Stmt> },
 
-    /// Цикл `while (cond) { body }`
-    While { cond: Expr, body: Vec<Stmt> },
+    /// Цикл `while (cond) { body } else { else_branch }`.
+    /// `else_branch` (может быть пустым) выполняется, если цикл
+    /// завершился сам, а не через `break`.
+    While {
+        cond: Expr,
+        body: Vec<Stmt>,
+        else_branch: Vec<Stmt>,
+    },
+
+    /// Цикл с постусловием `do { body } while (cond)`:
+    /// тело выполняется хотя бы один раз, условие проверяется после него.
+    DoWhile { body: Vec<Stmt>, cond: Expr },
+
+    /// `repeat count { body }` — выполнить тело ровно `count` раз
+    /// (без переменной-счётчика).
+    Repeat { count: Expr, body: Vec<Stmt> },
+
+    /// Бесконечный цикл `loop { body }`; выход — через `break` или `return`.
+    Loop { body: Vec<Stmt> },
 
     /// Простой "for" с условием:
     ///   for (cond) { body }
@@ -71,6 +167,7 @@ pub enum Stmt {
         cond: Option<Expr>,      // i < 10           (если None — считаем, что всегда true)
         step: Option<Box<Stmt>>, // i = i + 1        или любая ExprStmt/Assign/VarDecl
         body: Vec<Stmt>,
+        else_branch: Vec<Stmt>, // как у while: если цикл не прерван `break`
     },
 
     /// Цикл for-each:
@@ -83,38 +180,137 @@ pub enum Stmt {
     ///   - Int(n)  -> 0..n-1
     ///   - Str("abc") -> посимвольно
     ///   - List([...]) -> по элементам
+    ///   - Dict({...}) -> по ключам в порядке вставки
+    ///
+    /// Форма `for k, v in dict { ... }` (только для словарей) связывает
+    /// ещё и значение: тогда `value_name` — имя второй переменной.
     ForEach {
         var_name: String,
+        value_name: Option<String>,
         iter_expr: Expr,
         body: Vec<Stmt>,
+        /// Как у while: если цикл не прерван `break`.
+        else_branch: Vec<Stmt>,
+    },
+
+    /// Цикл по диапазону целых чисел:
+    ///
+    ///   for i in start..end { ... }            // start, start+1, ..., end-1
+    ///   for i in start..end step k { ... }     // start, start+k, ... (k < 0 — вниз)
+    ///
+    /// `end` не входит в диапазон; числа перебираются лениво, без списка.
+    ForRange {
+        var_name: String,
+        start: Expr,
+        end: Expr,
+        /// Шаг из `step k`; `None` — шаг 1.
+        step: Option<Expr>,
+        body: Vec<Stmt>,
+        /// Как у while: если цикл не прерван `break`.
+        else_branch: Vec<Stmt>,
+    },
+
+    /// Сопоставление значения с литералами:
+    ///
+    ///   match scrutinee {
+    ///       1 { ... }
+    ///       "two" { ... }
+    ///       else { ... }
+    ///   }
+    ///
+    /// Выполняется первая ветка, чей литерал равен `scrutinee`;
+    /// если ни одна не подошла — `default` (может быть пустым).
+    Match {
+        scrutinee: Expr,
+        arms: Vec<(Expr, Vec<Stmt>)>,
+        default: Vec<Stmt>,
+    },
+
+    /// Перехват ошибок:
+    ///
+    ///   try { body... } catch err_name { handler... }
+    ///
+    /// Если в `body` случилась ошибка исполнения, остаток `body` пропускается,
+    /// а `handler` выполняется с текстом ошибки в переменной `err_name`.
+    Try {
+        body: Vec<Stmt>,
+        err_name: String,
+        handler: Vec<Stmt>,
     },
 
+    /// `global x, y` внутри функции: до конца вызова `x` и `y` читаются
+    /// и присваиваются в глобальном окружении, а не в локальном.
+    Global(Vec<String>),
+
+    /// Объявление функции внутри блока (например, в теле другой функции):
+    /// видна только в этом блоке, начиная с места объявления.
+    FuncDecl(Function),
+
+    /// `break` — выйти из ближайшего цикла.
+    Break,
+
+    /// `continue` — перейти к следующей итерации ближайшего цикла.
+    Continue,
+
     /// Оператор `return` внутри функции.
     ///   return expr
     ///   return        // без значения
     Return(Option<Expr>),
 }
 
+impl Stmt {
+    /// Позиция оператора для сообщений об ошибках: у объявлений и
+    /// присваиваний — своя, у ветвлений и циклов — позиция условия
+    /// (или того, по чему идёт цикл), где и случаются их собственные ошибки.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Stmt::VarDecl { span, .. }
+            | Stmt::VarDestructure { span, .. }
+            | Stmt::Assign { span, .. }
+            | Stmt::AssignDestructure { span, .. } => *span,
+            Stmt::Branch { cond, .. }
+            | Stmt::ElseIfBranch { cond, .. }
+            | Stmt::While { cond, .. }
+            | Stmt::DoWhile { cond, .. } => cond.span(),
+            Stmt::For { cond, .. } => cond.as_ref().and_then(Expr::span),
+            Stmt::Repeat { count, .. } => count.span(),
+            Stmt::ForEach { iter_expr, .. } => iter_expr.span(),
+            Stmt::ForRange { start, .. } => start.span(),
+            Stmt::Match { scrutinee, .. } => scrutinee.span(),
+            Stmt::ExprStmt(expr) | Stmt::Return(Some(expr)) => expr.span(),
+            _ => None,
+        }
+    }
+}
+
 /// Описание пользовательской функции.
 ///
-///   func name(p1: T1, p2: T2, ...) {
+///   func name(p1: T1, p2: T2, ...) -> R {
 ///       body...
 ///   }
+///
+/// Если тело заканчивается оператором-выражением, его значение —
+/// результат функции (неявный `return`).
 #[derive(Debug, Clone)]
 pub struct Function {
     /// Имя функции.
     pub name: String,
     /// Параметры: (имя, тип).
     pub params: Vec<(String, Type)>,
+    /// Тип результата из `-> R`; `None` — не проверяется.
+    pub ret_type: Option<Type>,
     /// Тело функции — блок операторов.
     pub body: Vec<Stmt>,
 }
 
 /// Вся программа целиком:
+///  - список импортируемых файлов
 ///  - список объявленных функций
 ///  - список "глобальных" операторов (выполняются как main-скрипт)
 #[derive(Debug, Clone)]
 pub struct Program {
+    /// Пути из `import "file.rht"` (в порядке появления).
+    pub imports: Vec<String>,
     /// Все `func ... { ... }`.
     pub functions: Vec<Function>,
     /// Глобальные операторы вне функций.
@@ -128,14 +324,20 @@ pub enum Expr {
     /// Целочисленный литерал: `123`
     Int(i64),
 
+    /// Литерал с плавающей точкой: `1.5`
+    Float(f64),
+
     /// Логический литерал: `true` / `false`
     Bool(bool),
 
     /// Строковый литерал: `"hello"`
     Str(String),
 
+    /// Символьный литерал: `'a'`
+    Char(char),
+
     /// Использование переменной по имени: `x`
-    Var(String),
+    Var { name: String, span: Option<Span> },
 
     /// Бинарная операция:
     ///   left <op> right
@@ -143,15 +345,78 @@ pub enum Expr {
         left: Box<Expr>,
         op: BinOp,
         right: Box<Expr>,
+        span: Option<Span>,
+    },
+
+    /// Цепочка сравнений:
+    ///   first <op1> e1 <op2> e2 ...
+    /// Эквивалентна `first op1 e1 && e1 op2 e2 && ...`,
+    /// но каждый операнд вычисляется ровно один раз.
+    CompareChain {
+        first: Box<Expr>,
+        rest: Vec<(BinOp, Expr)>,
+        span: Option<Span>,
+    },
+
+    /// Унарная операция:
+    ///   <op> expr
+    Unary {
+        op: UnOp,
+        expr: Box<Expr>,
+        span: Option<Span>,
     },
 
     /// Вызов функции:
     ///   callee(arg1, arg2, ...)
-    Call { callee: String, args: Vec<Expr> },
+    /// Обычно `callee` — имя (`Expr::Var`), но вызвать можно результат
+    /// любого выражения: `f(1)(2)`, `xs[0](x)`.
+    Call {
+        callee: Box<Expr>,
+        args: Vec<Expr>,
+        span: Option<Span>,
+    },
+
+    /// Индексация:
+    ///   target[index]
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+        span: Option<Span>,
+    },
 
     /// Литерал списка:
     ///   [expr1, expr2, expr3, ...]
     ListLiteral(Vec<Expr>),
+
+    /// Литерал кортежа (от выражения в скобках отличается запятой):
+    ///   (expr1, expr2, ...)   (expr,)   ()
+    TupleLiteral(Vec<Expr>),
+
+    /// Литерал словаря:
+    ///   {key1: value1, key2: value2, ...}
+    DictLiteral(Vec<(Expr, Expr)>),
+
+    /// Анонимная функция:
+    ///   fn(p1: T1, ...) { body... }
+    /// Под `Rc`, чтобы каждое вычисление лямбды не клонировало её тело.
+    Lambda(Rc<Function>),
+}
+
+impl Expr {
+    /// Позиция выражения, если парсер её запомнил.
+    /// Есть у узлов, вычисление которых может упасть: переменных,
+    /// операций, вызовов и индексации (у литералов позиции нет).
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Expr::Var { span, .. }
+            | Expr::Binary { span, .. }
+            | Expr::CompareChain { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Index { span, .. } => *span,
+            _ => None,
+        }
+    }
 }
 
 /// Бинарные операторы.
@@ -168,4 +433,15 @@ pub enum BinOp {
     LtEq,  // <=
     Gt,    // >
     GtEq,  // >=
+
+    // Логические операторы вычисляются лениво (short-circuit).
+    And, // && / and
+    Or,  // || / or
+}
+
+/// Унарные операторы.
+#[derive(Debug, Clone, Copy)]
+pub enum UnOp {
+    Not, // ! / not
+    Neg, // -
 }
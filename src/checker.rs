@@ -0,0 +1,750 @@
+// checker.rs
+//
+// Статическая проверка программы без исполнения (флаг `--check`):
+//  - тип значения в `var x: T = ...`, в присваивании и в `return`
+//    не совпадает с объявленным;
+//  - используется или присваивается необъявленная переменная;
+//  - присваивается неизменяемая переменная;
+//  - вызывается неизвестная функция;
+//  - пользовательской функции передано не то число аргументов.
+//
+// Тип выражения выводится, только когда он виден из кода (литералы,
+// переменные с известным типом, арифметика, функции с `-> T`); всё
+// остальное считается неизвестным и не проверяется. Проверка не должна
+// ругаться на программу, которая исполнится без ошибок, поэтому в
+// сомнительных случаях она молчит:
+//  - область видимости в языке динамическая (функция видит переменные
+//    вызывающего), поэтому внутри функций видны все переменные, объявленные
+//    где-либо в программе;
+//  - если в программе есть `import`, неизвестные функции и имена
+//    не проверяются (импортированные файлы не читаются).
+
+use crate::ast::{BinOp, Expr, Function, Program, Stmt, Type, UnOp};
+use crate::interpreter::STATEFUL_BUILTIN_NAMES;
+use crate::stdlib::BUILTIN_NAMES;
+use std::collections::HashMap;
+
+/// Проверить программу. Возвращает сообщения об ошибках
+/// (пустой список — ошибок не найдено).
+pub fn check(program: &Program) -> Vec<String> {
+    let mut checker = Checker {
+        functions: program
+            .functions
+            .iter()
+            .map(|f| (f.name.clone(), Signature::of(f)))
+            .collect(),
+        globals: HashMap::new(),
+        scopes: vec![Scope::default()],
+        ret_types: Vec::new(),
+        has_imports: !program.imports.is_empty(),
+        diagnostics: Vec::new(),
+    };
+
+    // функциям видны переменные, где бы они ни были объявлены
+    collect_declarations(&program.stmts, &mut checker.globals);
+    for func in &program.functions {
+        collect_function_declarations(func, &mut checker.globals);
+    }
+
+    checker.check_block(&program.stmts);
+    for func in &program.functions {
+        checker.check_function(func);
+    }
+    checker.diagnostics
+}
+
+/// Что известно о переменной.
+#[derive(Clone)]
+struct VarInfo {
+    /// `None` — тип неизвестен.
+    ty: Option<Type>,
+    mutable: bool,
+}
+
+/// Что нужно знать о функции для проверки вызова.
+#[derive(Clone)]
+struct Signature {
+    params: usize,
+    ret_type: Option<Type>,
+}
+
+impl Signature {
+    fn of(func: &Function) -> Self {
+        Self {
+            params: func.params.len(),
+            ret_type: func.ret_type.clone(),
+        }
+    }
+}
+
+/// Один scope, как в интерпретаторе: переменные и локальные функции.
+#[derive(Default)]
+struct Scope {
+    vars: HashMap<String, VarInfo>,
+    functions: HashMap<String, Signature>,
+}
+
+struct Checker {
+    /// Функции верхнего уровня.
+    functions: HashMap<String, Signature>,
+    /// Все переменные, объявленные в программе (для тел функций).
+    globals: HashMap<String, VarInfo>,
+    scopes: Vec<Scope>,
+    /// Объявленные типы результата функций, тела которых сейчас проверяются.
+    ret_types: Vec<(String, Option<Type>)>,
+    has_imports: bool,
+    diagnostics: Vec<String>,
+}
+
+/// Собрать переменные, объявленные в операторах (включая вложенные блоки).
+/// Тип не запоминаем: одно имя может быть объявлено в разных блоках по-разному.
+fn collect_declarations(stmts: &[Stmt], out: &mut HashMap<String, VarInfo>) {
+    let declare = |name: &str, mutable: bool, out: &mut HashMap<String, VarInfo>| {
+        let info = out
+            .entry(name.to_string())
+            .or_insert(VarInfo { ty: None, mutable });
+        info.mutable |= mutable;
+    };
+    for stmt in stmts {
+        match stmt {
+            Stmt::VarDecl { name, mutable, .. } => declare(name, *mutable, out),
+            Stmt::VarDestructure { names, mutable, .. } => {
+                for name in names {
+                    declare(name, *mutable, out);
+                }
+            }
+            Stmt::Branch {
+                then_branch,
+                else_if_branches,
+                else_branch,
+                ..
+            } => {
+                collect_declarations(then_branch, out);
+                collect_declarations(else_if_branches, out);
+                collect_declarations(else_branch, out);
+            }
+            Stmt::ElseIfBranch { then_branch, .. } => collect_declarations(then_branch, out),
+            Stmt::While {
+                body, else_branch, ..
+            } => {
+                collect_declarations(body, out);
+                collect_declarations(else_branch, out);
+            }
+            Stmt::ForEach {
+                var_name,
+                value_name,
+                body,
+                else_branch,
+                ..
+            } => {
+                declare(var_name, false, out);
+                if let Some(value_name) = value_name {
+                    declare(value_name, false, out);
+                }
+                collect_declarations(body, out);
+                collect_declarations(else_branch, out);
+            }
+            Stmt::ForRange {
+                var_name,
+                body,
+                else_branch,
+                ..
+            } => {
+                declare(var_name, false, out);
+                collect_declarations(body, out);
+                collect_declarations(else_branch, out);
+            }
+            Stmt::For {
+                init,
+                body,
+                else_branch,
+                ..
+            } => {
+                if let Some(init) = init {
+                    collect_declarations(std::slice::from_ref(init.as_ref()), out);
+                }
+                collect_declarations(body, out);
+                collect_declarations(else_branch, out);
+            }
+            Stmt::DoWhile { body, .. } | Stmt::Repeat { body, .. } | Stmt::Loop { body } => {
+                collect_declarations(body, out)
+            }
+            Stmt::Match { arms, default, .. } => {
+                for (_, body) in arms {
+                    collect_declarations(body, out);
+                }
+                collect_declarations(default, out);
+            }
+            Stmt::Try {
+                body,
+                err_name,
+                handler,
+            } => {
+                collect_declarations(body, out);
+                declare(err_name, false, out);
+                collect_declarations(handler, out);
+            }
+            Stmt::FuncDecl(func) => collect_function_declarations(func, out),
+            _ => {}
+        }
+    }
+}
+
+/// Параметры и переменные тела функции.
+fn collect_function_declarations(func: &Function, out: &mut HashMap<String, VarInfo>) {
+    for (name, _) in &func.params {
+        out.entry(name.clone()).or_insert(VarInfo {
+            ty: None,
+            mutable: false,
+        });
+    }
+    collect_declarations(&func.body, out);
+}
+
+/// Подходит ли значение статического типа `actual` переменной типа `declared`.
+/// Тип элементов списка статически не известен, поэтому `list[T]` принимает любой список.
+/// Правила должны совпадать с `Interpreter::value_matches_type` — меняются вместе.
+fn compatible(declared: &Type, actual: &Type) -> bool {
+    match (declared, actual) {
+        (Type::List(_), Type::List(_)) => true,
+        (declared, actual) => declared == actual,
+    }
+}
+
+impl Checker {
+    fn report(&mut self, msg: String) {
+        self.diagnostics.push(msg);
+    }
+
+    fn in_function(&self) -> bool {
+        !self.ret_types.is_empty()
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn pop_scope(&mut self) {
+        self.scopes.pop().expect("scope stack underflow");
+    }
+
+    fn declare(&mut self, name: &str, ty: Option<Type>, mutable: bool) {
+        self.scopes
+            .last_mut()
+            .expect("no scope")
+            .vars
+            .insert(name.to_string(), VarInfo { ty, mutable });
+    }
+
+    fn lookup_var(&self, name: &str) -> Option<VarInfo> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.vars.get(name).cloned())
+            .or_else(|| {
+                self.in_function()
+                    .then(|| self.globals.get(name).cloned())
+                    .flatten()
+            })
+    }
+
+    fn lookup_function(&self, name: &str) -> Option<Signature> {
+        self.scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.functions.get(name).cloned())
+            .or_else(|| self.functions.get(name).cloned())
+    }
+
+    /* ========================= ФУНКЦИИ ========================= */
+
+    /// Тело функции проверяется в собственном scope с параметрами.
+    /// Текущие scope остаются видны (так локальная функция видит внешние переменные).
+    fn check_function(&mut self, func: &Function) {
+        self.push_scope();
+        for (name, ty) in &func.params {
+            self.declare(name, Some(ty.clone()), false);
+        }
+        self.ret_types
+            .push((func.name.clone(), func.ret_type.clone()));
+        self.check_stmts(&func.body);
+        self.ret_types.pop();
+        self.pop_scope();
+    }
+
+    /* ========================= ОПЕРАТОРЫ ========================= */
+
+    fn check_block(&mut self, stmts: &[Stmt]) {
+        self.push_scope();
+        self.check_stmts(stmts);
+        self.pop_scope();
+    }
+
+    fn check_stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.check_stmt(stmt);
+        }
+    }
+
+    fn check_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::VarDecl {
+                name,
+                mutable,
+                ty,
+                init,
+                ..
+            } => {
+                let actual = self.infer(init);
+                let ty = match (ty, actual) {
+                    (Some(declared), Some(actual)) => {
+                        if !compatible(declared, &actual) {
+                            self.report(format!(
+                                "type error: variable '{}' declared as {}, but value is {}",
+                                name, declared, actual
+                            ));
+                        }
+                        Some(declared.clone())
+                    }
+                    (Some(declared), None) => Some(declared.clone()),
+                    (None, actual) => actual,
+                };
+                self.declare(name, ty, *mutable);
+            }
+
+            Stmt::VarDestructure {
+                names,
+                mutable,
+                init,
+                ..
+            } => {
+                self.infer(init);
+                for name in names {
+                    self.declare(name, None, *mutable);
+                }
+            }
+
+            Stmt::ExprStmt(expr) => {
+                self.infer(expr);
+            }
+
+            Stmt::Assign { name, expr, .. } => {
+                let actual = self.infer(expr);
+                self.check_assign(name, actual);
+            }
+
+            Stmt::AssignDestructure { names, expr, .. } => {
+                self.infer(expr);
+                for name in names {
+                    self.check_assign(name, None);
+                }
+            }
+
+            Stmt::Branch {
+                cond,
+                then_branch,
+                else_if_branches,
+                else_branch,
+            } => {
+                self.infer(cond);
+                self.check_block(then_branch);
+                self.check_stmts(else_if_branches);
+                self.check_block(else_branch);
+            }
+
+            Stmt::ElseIfBranch { cond, then_branch } => {
+                self.infer(cond);
+                self.check_block(then_branch);
+            }
+
+            Stmt::While {
+                cond,
+                body,
+                else_branch,
+            } => {
+                self.infer(cond);
+                self.check_block(body);
+                self.check_block(else_branch);
+            }
+
+            Stmt::DoWhile { body, cond } => {
+                self.check_block(body);
+                self.infer(cond);
+            }
+
+            Stmt::Repeat { count, body } => {
+                self.infer(count);
+                self.check_block(body);
+            }
+
+            Stmt::Loop { body } => self.check_block(body),
+
+            Stmt::For {
+                init,
+                cond,
+                step,
+                body,
+                else_branch,
+            } => {
+                self.push_scope();
+                if let Some(init) = init {
+                    self.check_stmt(init);
+                }
+                if let Some(cond) = cond {
+                    self.infer(cond);
+                }
+                self.check_block(body);
+                if let Some(step) = step {
+                    self.check_stmt(step);
+                }
+                self.check_block(else_branch);
+                self.pop_scope();
+            }
+
+            Stmt::ForEach {
+                var_name,
+                value_name,
+                iter_expr,
+                body,
+                else_branch,
+            } => {
+                // по числу идут int, по строке — char, остальное статически неизвестно
+                let item_ty = match self.infer(iter_expr) {
+                    Some(Type::Int) => Some(Type::Int),
+                    Some(Type::Str) => Some(Type::Char),
+                    _ => None,
+                };
+                self.push_scope();
+                self.declare(var_name, item_ty, false);
+                if let Some(value_name) = value_name {
+                    self.declare(value_name, None, false);
+                }
+                self.check_block(body);
+                self.check_block(else_branch);
+                self.pop_scope();
+            }
+
+            Stmt::ForRange {
+                var_name,
+                start,
+                end,
+                step,
+                body,
+                else_branch,
+            } => {
+                self.infer(start);
+                self.infer(end);
+                if let Some(step) = step {
+                    self.infer(step);
+                }
+                self.push_scope();
+                self.declare(var_name, Some(Type::Int), false);
+                self.check_block(body);
+                self.check_block(else_branch);
+                self.pop_scope();
+            }
+
+            Stmt::Match {
+                scrutinee,
+                arms,
+                default,
+            } => {
+                self.infer(scrutinee);
+                for (pattern, body) in arms {
+                    self.infer(pattern);
+                    self.check_block(body);
+                }
+                self.check_block(default);
+            }
+
+            Stmt::Try {
+                body,
+                err_name,
+                handler,
+            } => {
+                self.check_block(body);
+                self.push_scope();
+                self.declare(err_name, Some(Type::Str), false);
+                self.check_block(handler);
+                self.pop_scope();
+            }
+
+            Stmt::Global(names) => {
+                for name in names {
+                    if !self.globals.contains_key(name) {
+                        self.report(format!("global variable '{}' is not declared", name));
+                    }
+                }
+            }
+
+            Stmt::FuncDecl(func) => {
+                self.scopes
+                    .last_mut()
+                    .expect("no scope")
+                    .functions
+                    .insert(func.name.clone(), Signature::of(func));
+                self.check_function(func);
+            }
+
+            Stmt::Return(expr) => {
+                let actual = expr.as_ref().and_then(|expr| self.infer(expr));
+                if let Some((name, Some(declared))) = self.ret_types.last().cloned()
+                    && let Some(actual) = actual
+                    && !compatible(&declared, &actual)
+                {
+                    self.report(format!(
+                        "type error: function '{}' declared to return {}, but returned {}",
+                        name, declared, actual
+                    ));
+                }
+            }
+
+            Stmt::Break | Stmt::Continue => {}
+        }
+    }
+
+    fn check_assign(&mut self, name: &str, actual: Option<Type>) {
+        let Some(info) = self.lookup_var(name) else {
+            self.report(format!("assignment to undeclared variable '{}'", name));
+            return;
+        };
+        if !info.mutable {
+            self.report(format!("cannot assign to immutable variable '{}'", name));
+        } else if let (Some(declared), Some(actual)) = (info.ty, actual)
+            && !compatible(&declared, &actual)
+        {
+            self.report(format!(
+                "type error: variable '{}' declared as {}, but value is {}",
+                name, declared, actual
+            ));
+        }
+    }
+
+    /* ========================= ВЫРАЖЕНИЯ ========================= */
+
+    /// Проверить выражение и вывести его тип (`None` — неизвестен).
+    fn infer(&mut self, expr: &Expr) -> Option<Type> {
+        match expr {
+            Expr::Int(_) => Some(Type::Int),
+            Expr::Float(_) => Some(Type::Float),
+            Expr::Bool(_) => Some(Type::Bool),
+            Expr::Str(_) => Some(Type::Str),
+            Expr::Char(_) => Some(Type::Char),
+
+            Expr::Var { name, .. } => {
+                if let Some(info) = self.lookup_var(name) {
+                    info.ty
+                } else if self.lookup_function(name).is_some() {
+                    Some(Type::Func)
+                } else {
+                    // имя может оказаться функцией из импортированного файла
+                    if !self.has_imports {
+                        self.report(format!("Undefined variable {}", name));
+                    }
+                    None
+                }
+            }
+
+            Expr::Binary {
+                left, op, right, ..
+            } => {
+                let l = self.infer(left);
+                let r = self.infer(right);
+                Self::binary_type(op, l?, r?)
+            }
+
+            Expr::CompareChain { first, rest, .. } => {
+                self.infer(first);
+                for (_, expr) in rest {
+                    self.infer(expr);
+                }
+                Some(Type::Bool)
+            }
+
+            Expr::Unary { op, expr, .. } => {
+                let ty = self.infer(expr);
+                match op {
+                    UnOp::Not => Some(Type::Bool),
+                    UnOp::Neg => ty.filter(|ty| matches!(ty, Type::Int | Type::Float)),
+                }
+            }
+
+            Expr::Call { callee, args, .. } => {
+                for arg in args {
+                    self.infer(arg);
+                }
+                match callee.as_ref() {
+                    Expr::Var { name, .. } => self.check_call(name, args.len()),
+                    callee => {
+                        self.infer(callee);
+                        None
+                    }
+                }
+            }
+
+            Expr::Index { target, index, .. } => {
+                self.infer(target);
+                self.infer(index);
+                None
+            }
+
+            Expr::ListLiteral(items) => {
+                for item in items {
+                    self.infer(item);
+                }
+                Some(Type::List(None))
+            }
+
+            Expr::TupleLiteral(items) => {
+                for item in items {
+                    self.infer(item);
+                }
+                Some(Type::Tuple)
+            }
+
+            Expr::DictLiteral(pairs) => {
+                for (key, value) in pairs {
+                    self.infer(key);
+                    self.infer(value);
+                }
+                Some(Type::Dict)
+            }
+
+            Expr::Lambda(func) => {
+                self.check_function(func);
+                Some(Type::Func)
+            }
+        }
+    }
+
+    /// Вызов по имени — в том же порядке поиска, что и в `Interpreter::eval_call`.
+    /// Возвращает тип результата, если он известен.
+    fn check_call(&mut self, name: &str, arg_count: usize) -> Option<Type> {
+        // переменная, в которой может лежать функция: сигнатура неизвестна
+        if let Some(info) = self.lookup_var(name)
+            && matches!(info.ty, None | Some(Type::Func))
+        {
+            return None;
+        }
+
+        if let Some(sig) = self.lookup_function(name) {
+            if sig.params != arg_count {
+                self.report(format!(
+                    "function '{}' expected {} arguments, got {}",
+                    name, sig.params, arg_count
+                ));
+            }
+            return sig.ret_type;
+        }
+
+        let is_builtin = BUILTIN_NAMES.contains(&name) || STATEFUL_BUILTIN_NAMES.contains(&name);
+        if !is_builtin && !self.has_imports {
+            self.report(format!("Unknown function '{}'", name));
+        }
+        None
+    }
+
+    /// Тип результата бинарной операции над операндами известных типов.
+    /// Правила должны совпадать с `Interpreter::eval_bin` — меняются вместе.
+    /// `None` — если операция не определена или тип не выводится; ошибки
+    /// операций не проверяются.
+    fn binary_type(op: &BinOp, l: Type, r: Type) -> Option<Type> {
+        use Type::*;
+        match op {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div => match (l, r) {
+                (Int, Int) => Some(Int),
+                (Int | Float, Int | Float) => Some(Float),
+                (Str | Char, Str | Char) if matches!(op, BinOp::Add) => Some(Str),
+                _ => None,
+            },
+            BinOp::Eq
+            | BinOp::NotEq
+            | BinOp::Lt
+            | BinOp::LtEq
+            | BinOp::Gt
+            | BinOp::GtEq
+            | BinOp::And
+            | BinOp::Or => Some(Bool),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn check_source(src: &str) -> Vec<String> {
+        let mut parser = Parser::new(Lexer::new(src));
+        check(&parser.parse_program())
+    }
+
+    #[test]
+    fn well_typed_program_checks_clean() {
+        let src = "func add(a: int, b: int) -> int {
+    return a + b
+}
+func show() {
+    print(total)
+}
+var mut total: int = add(1, 2)
+for i in 0..3 {
+    total = total + i
+}
+var xs = map([1, 2], fn(x: int) { return x * 2 })
+try {
+    raise(\"boom\")
+} catch e {
+    print(e, len(xs))
+}
+show()";
+        assert_eq!(check_source(src), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_unknown_function_and_wrong_argument_count() {
+        let src = "func add(a: int, b: int) {
+    return a + b
+}
+prnt(add(1))";
+        assert_eq!(
+            check_source(src),
+            vec![
+                "function 'add' expected 2 arguments, got 1".to_string(),
+                "Unknown function 'prnt'".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_type_mismatches_and_undeclared_variables() {
+        let src = "var x: int = \"one\"
+var mut y = 1
+y = 2.5
+var z = 1
+z = 2
+print(w)
+missing = 3
+func f() -> str {
+    return 1
+}";
+        assert_eq!(
+            check_source(src),
+            vec![
+                "type error: variable 'x' declared as int, but value is str".to_string(),
+                "type error: variable 'y' declared as int, but value is float".to_string(),
+                "cannot assign to immutable variable 'z'".to_string(),
+                "Undefined variable w".to_string(),
+                "assignment to undeclared variable 'missing'".to_string(),
+                "type error: function 'f' declared to return str, but returned int".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn programs_with_imports_do_not_report_unknown_functions() {
+        let src = "import \"lib.rht\"\nhelper(1)\nvar f = helper\nf(2)";
+        assert_eq!(check_source(src), Vec::<String>::new());
+    }
+}
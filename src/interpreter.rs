@@ -6,34 +6,399 @@
 //  - исполняет операторы (Stmt)
 //  - вычисляет выражения (Expr)
 //  - вызывает встроенные и пользовательские функции
+//
+// Ошибки исполнения не паникуют, а возвращаются как `RuntimeError`
+// и пробрасываются наверх через `?` до `Interpreter::run`.
 
-use crate::ast::{BinOp, Expr, Function, Program, Stmt, Type};
+use crate::ast::{BinOp, Expr, Function, Program, Span, Stmt, Type, UnOp};
+use crate::parser;
 use crate::stdlib;
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    cell::RefCell,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+    fmt::Debug,
+    fs,
+    hash::Hash,
+    io::{self, Read, Write},
+    mem,
+    rc::Rc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
 /// Все возможные значения языка на этапе исполнения.
 #[derive(Clone, Debug)]
 pub enum Value {
     Int(i64),
+    Float(f64),
     Bool(bool),
     Str(String),
-    List(Vec<Value>),
+    /// Один символ: литерал `'a'` или элемент строки в `for ch in s`.
+    Char(char),
+    /// Список разделяется между копиями через `Rc`: чтение переменной
+    /// со списком не копирует элементы. Списки неизменяемы, поэтому
+    /// "изменение" (`push`, ...) всегда строит новый `Vec`.
+    List(Rc<Vec<Value>>),
+    /// Словарь `{key: value, ...}`; как и список, неизменяемый и общий через `Rc`.
+    Dict(Rc<Dict>),
+    /// Кортеж `(a, b, ...)`: фиксированный набор значений любых типов.
+    Tuple(Rc<Vec<Value>>),
+    /// Функция как значение: лямбда `fn(...) { ... }` или имя функции.
+    Func(Rc<Closure>),
 
     /// "Пустое" значение — аналог `void` / `()` / отсутствия результата.
     Unit,
 }
 
+impl Value {
+    /// Список из готового вектора элементов.
+    pub fn list(items: Vec<Value>) -> Value {
+        Value::List(Rc::new(items))
+    }
+}
+
+/// Структурное равенство: списки равны поэлементно, словари — по набору
+/// пар (порядок вставки не важен). Значения разных типов не равны.
+/// Единое определение для `==`, `match`, `contains`, `index_of` и ключей словаря.
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Str(a), Value::Str(b)) => a == b,
+            (Value::Char(a), Value::Char(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a == b,
+            (Value::Dict(a), Value::Dict(b)) => a == b,
+            (Value::Tuple(a), Value::Tuple(b)) => a == b,
+            // функции равны, только если это одно и то же значение
+            (Value::Func(a), Value::Func(b)) => Rc::ptr_eq(a, b),
+            (Value::Unit, Value::Unit) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Функция-значение.
+///
+/// У лямбды `env` — окружения, видимые в месте её создания (по ссылке:
+/// лямбда видит и последующие изменения внешних переменных). Вызов
+/// исполняет тело поверх них, а не поверх стека вызывающего кода.
+/// У функции, взятой по имени, `env` нет: она вызывается как обычно.
+pub struct Closure {
+    pub func: Rc<Function>,
+    env: Option<Vec<Rc<RefCell<Scope>>>>,
+}
+
+impl Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // окружение не печатаем: в нём может лежать сама эта функция
+        write!(f, "<fn {}>", self.func.name)
+    }
+}
+
+/// Ключ словаря — "хешируемая" часть `Value`: числа, bool, строки
+/// и списки / кортежи из них. `Unit` и словари ключами быть не могут.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum DictKey {
+    Int(i64),
+    Bool(bool),
+    Str(String),
+    Char(char),
+    List(Vec<DictKey>),
+    Tuple(Vec<DictKey>),
+}
+
+impl DictKey {
+    pub fn from_value(value: &Value) -> Result<DictKey, RuntimeError> {
+        match value {
+            Value::Int(n) => Ok(DictKey::Int(*n)),
+            Value::Bool(b) => Ok(DictKey::Bool(*b)),
+            Value::Str(s) => Ok(DictKey::Str(s.clone())),
+            Value::Char(c) => Ok(DictKey::Char(*c)),
+            Value::List(items) => Ok(DictKey::List(
+                items
+                    .iter()
+                    .map(DictKey::from_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            Value::Tuple(items) => Ok(DictKey::Tuple(
+                items
+                    .iter()
+                    .map(DictKey::from_value)
+                    .collect::<Result<_, _>>()?,
+            )),
+            other => error(format!("unhashable value {:?} cannot be a dict key", other)),
+        }
+    }
+}
+
+/// Словарь с сохранением порядка вставки: пары лежат в `entries`,
+/// а `index` по ключу находит позицию пары.
+#[derive(Clone, Debug, Default)]
+pub struct Dict {
+    entries: Vec<(Value, Value)>,
+    index: HashMap<DictKey, usize>,
+}
+
+impl Dict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Добавить пару или заменить значение у существующего ключа
+    /// (позиция ключа при этом не меняется).
+    pub fn insert(&mut self, key: Value, value: Value) -> Result<(), RuntimeError> {
+        let hashed = DictKey::from_value(&key)?;
+        match self.index.get(&hashed) {
+            Some(&pos) => self.entries[pos].1 = value,
+            None => {
+                self.index.insert(hashed, self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, key: &Value) -> Result<Option<&Value>, RuntimeError> {
+        let hashed = DictKey::from_value(key)?;
+        Ok(self.index.get(&hashed).map(|&pos| &self.entries[pos].1))
+    }
+
+    pub fn contains_key(&self, key: &Value) -> Result<bool, RuntimeError> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Пары (ключ, значение) в порядке вставки.
+    pub fn iter(&self) -> impl Iterator<Item = &(Value, Value)> {
+        self.entries.iter()
+    }
+}
+
+impl PartialEq for Dict {
+    fn eq(&self, other: &Dict) -> bool {
+        self.len() == other.len()
+            && self.entries.iter().all(|(key, value)| {
+                matches!(other.get(key), Ok(Some(other_value)) if other_value == value)
+            })
+    }
+}
+
+/// Причина, по которой исполнение программы прервалось.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RuntimeError {
+    /// Ошибка времени выполнения (неизвестная переменная, ошибка типов, ...)
+    /// и место в исходнике, где она случилась, если оно известно.
+    Error { msg: String, span: Option<Span> },
+    /// Программа вызвала `exit(code)`. Это не ошибка, а сигнал завершения:
+    /// он раскручивает стек так же, как ошибка, а решение о выходе из
+    /// процесса принимает тот, кто запустил интерпретатор.
+    Exit(i32),
+    /// Исчерпан бюджет итераций циклов (см. `Interpreter::with_max_iterations`).
+    /// Как и `exit`, не перехватывается `try`: иначе чужой код мог бы
+    /// поймать ошибку и крутиться дальше.
+    IterationLimit(u64),
+}
+
+impl RuntimeError {
+    pub fn new(msg: impl Into<String>) -> Self {
+        RuntimeError::Error {
+            msg: msg.into(),
+            span: None,
+        }
+    }
+
+    /// Привязать ошибку к месту в исходнике, если она ещё не привязана.
+    /// Ошибка поднимается от узла к узлу, поэтому место получает самый
+    /// внутренний узел с известной позицией — тот, на котором она случилась.
+    pub fn at(self, span: Option<Span>) -> Self {
+        match self {
+            RuntimeError::Error { msg, span: None } => RuntimeError::Error { msg, span },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuntimeError::Error {
+                msg,
+                span: Some(span),
+            } => write!(f, "{} at {}", msg, span),
+            RuntimeError::Error { msg, span: None } => write!(f, "{}", msg),
+            RuntimeError::Exit(code) => write!(f, "exit({})", code),
+            RuntimeError::IterationLimit(limit) => {
+                write!(f, "iteration limit exceeded ({} loop iterations)", limit)
+            }
+        }
+    }
+}
+
+/// Короткая запись для `Err(RuntimeError::new(...))`.
+pub fn error<T>(msg: impl Into<String>) -> Result<T, RuntimeError> {
+    Err(RuntimeError::new(msg))
+}
+
+/// Чем закончилось выполнение оператора или блока.
+#[derive(Debug)]
+enum Flow {
+    /// Обычное выполнение — переходим к следующему оператору.
+    Normal,
+    /// Встретился `return` — значение пробрасывается до вызова функции.
+    Return(Value),
+    /// `break` — пробрасывается до ближайшего цикла.
+    Break,
+    /// `continue` — пробрасывается до ближайшего цикла.
+    Continue,
+}
+
+/// Что делать циклу после очередного выполнения тела.
+enum LoopStep {
+    /// Следующая итерация (тело закончилось само или через `continue`).
+    Next,
+    /// Выйти из цикла через `break` — `else` цикла не выполняется.
+    Break,
+    /// Выйти из цикла, пробросив `return` наверх.
+    Return(Value),
+}
+
+impl From<Flow> for LoopStep {
+    fn from(flow: Flow) -> Self {
+        match flow {
+            Flow::Normal | Flow::Continue => LoopStep::Next,
+            Flow::Break => LoopStep::Break,
+            Flow::Return(v) => LoopStep::Return(v),
+        }
+    }
+}
+
+/// Переменная в окружении: значение, флаг изменяемости и статический тип.
+/// `var x = ...` — неизменяемая, `var mut x = ...` — можно переприсваивать.
+/// Тип фиксируется при объявлении и проверяется при каждом присваивании.
+#[derive(Clone, Debug)]
+struct Binding {
+    value: Value,
+    mutable: bool,
+    ty: Type,
+}
+
+/// Один scope: переменные и локальные функции (`func`, объявленная внутри блока).
+/// Локальная функция видна, пока жив её scope.
+#[derive(Default)]
+struct Scope {
+    vars: HashMap<String, Binding>,
+    functions: HashMap<String, Rc<Function>>,
+}
+
+/// Чтение исходника по пути из `import`.
+type SourceLoader = dyn Fn(&str) -> io::Result<String>;
+
 /// Главная структура интерпретатора.
 /// Хранит:
 ///  - стек окружений переменных (env_stack)
 ///  - таблицу объявленных функций (functions)
+///  - состояние встроенных функций, которым оно нужно (clock, random, argv, ...)
 pub struct Interpreter {
-    /// Стек окружений: каждый `HashMap` — отдельный scope.
+    /// Стек окружений: каждый `Scope` — отдельный scope.
     /// Верхний (последний) элемент — текущий scope.
-    env_stack: Vec<HashMap<String, Value>>,
+    /// Scope лежат за `Rc<RefCell<...>>`, чтобы лямбды могли захватывать их по ссылке.
+    env_stack: Vec<Rc<RefCell<Scope>>>,
+
+    /// Имена из `global x` — по одному набору на каждый активный вызов
+    /// функции (последний — у текущего вызова).
+    frame_globals: Vec<HashSet<String>>,
+
+    /// Функции верхнего уровня: имя -> определение.
+    /// Хранятся за `Rc`, чтобы вызов не клонировал тело функции.
+    functions: HashMap<String, Rc<Function>>,
+
+    /// Момент создания интерпретатора — точка отсчёта для `clock()`.
+    started: Instant,
+
+    /// Состояние генератора псевдослучайных чисел (xorshift64) для `random()`.
+    /// Никогда не равно нулю: из нуля xorshift выдаёт только нули.
+    rng_state: u64,
+
+    /// Куда пишут `print` и его варианты (по умолчанию — stdout).
+    out: Box<dyn Write>,
+
+    /// Откуда читают `read_all` / `read_lines` (по умолчанию — stdin).
+    input: Box<dyn Read>,
 
-    /// Пользовательские функции: имя -> определение.
-    functions: HashMap<String, Function>,
+    /// Аргументы командной строки программы (то, что идёт после пути к `.rht`).
+    args: Vec<String>,
+
+    /// Как получить текст файла по пути из `import` (по умолчанию — с диска).
+    loader: Box<SourceLoader>,
+
+    /// Условия `if` / `while` / ... принимают не только bool, а любое
+    /// значение по правилам `bool(x)` (см. `with_truthy_conditions`).
+    truthy_conditions: bool,
+
+    /// Сколько раз за запуск можно выполнить тело цикла (`None` — без ограничений),
+    /// и сколько раз оно уже выполнено (см. `with_max_iterations`).
+    max_iterations: Option<u64>,
+    iterations: u64,
+}
+
+/// Имена функций из `call_stateful_builtin` (см. `stdlib::BUILTIN_NAMES`).
+pub(crate) const STATEFUL_BUILTIN_NAMES: &[&str] = &[
+    "clock",
+    "argv",
+    "read_all",
+    "read_lines",
+    "seed",
+    "random",
+    "map",
+    "map2",
+    "filter",
+    "max_by",
+    "min_by",
+    "sort_by",
+    "group_by",
+    "bench",
+    "dispatch",
+];
+
+/// Запасное ненулевое состояние генератора (для `seed(0)`).
+const DEFAULT_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Начальное состояние генератора, пока программа не вызвала `seed(n)`:
+/// берём текущее время, чтобы запуски отличались друг от друга.
+fn initial_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0);
+    if nanos == 0 { DEFAULT_SEED } else { nanos }
+}
+
+/// Расстояние Левенштейна: сколько вставок, удалений и замен символов
+/// нужно, чтобы получить `b` из `a`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Interpreter {
@@ -42,14 +407,38 @@ impl Interpreter {
     /// Создаём интерпретатор с глобальным окружением.
     pub fn new() -> Self {
         Self {
-            env_stack: vec![HashMap::new()], // глобальное окружение
+            env_stack: vec![Rc::default()], // глобальное окружение
+            frame_globals: Vec::new(),
             functions: HashMap::new(),
+            started: Instant::now(),
+            rng_state: initial_seed(),
+            out: Box::new(io::stdout()),
+            input: Box::new(io::stdin()),
+            args: Vec::new(),
+            loader: Box::new(|path| fs::read_to_string(path)),
+            truthy_conditions: false,
+            max_iterations: None,
+            iterations: 0,
         }
     }
 
+    /// Перенаправить вывод программы (например, в буфер для тестов).
+    #[allow(dead_code)] // main.rs пишет в stdout по умолчанию
+    pub fn with_output(mut self, out: impl Write + 'static) -> Self {
+        self.out = Box::new(out);
+        self
+    }
+
+    /// Подменить ввод программы (например, строкой в тестах).
+    #[allow(dead_code)] // main.rs читает stdin по умолчанию
+    pub fn with_input(mut self, input: impl Read + 'static) -> Self {
+        self.input = Box::new(input);
+        self
+    }
+
     /// Входим в новый scope (например, при входе в блок или функцию).
     fn push_env(&mut self) {
-        self.env_stack.push(HashMap::new());
+        self.env_stack.push(Rc::default());
     }
 
     /// Выходим из scope.
@@ -58,92 +447,259 @@ impl Interpreter {
     }
 
     /// Объявляем новую переменную в текущем scope.
-    fn define_var(&mut self, name: String, value: Value) {
+    fn define_var(&mut self, name: String, value: Value, mutable: bool, ty: Type) {
         self.env_stack
-            .last_mut()
+            .last()
             .expect("no environment")
-            .insert(name, value);
+            .borrow_mut()
+            .vars
+            .insert(name, Binding { value, mutable, ty });
+    }
+
+    /// Объявляем локальную функцию в текущем scope.
+    fn define_function(&mut self, func: &Function) {
+        self.env_stack
+            .last()
+            .expect("no environment")
+            .borrow_mut()
+            .functions
+            .insert(func.name.clone(), Rc::new(func.clone()));
+    }
+
+    /// Ищем функцию: сначала локальные (по стеку сверху вниз), потом верхнего уровня.
+    fn find_function(&self, name: &str) -> Option<Rc<Function>> {
+        self.env_stack
+            .iter()
+            .rev()
+            .find_map(|env| env.borrow().functions.get(name).cloned())
+            .or_else(|| self.functions.get(name).cloned())
     }
 
     /// Присваиваем существующей переменной (ищем по стеку сверху вниз).
-    fn assign_var(&mut self, name: &str, value: Value) {
-        for env in self.env_stack.iter_mut().rev() {
-            if env.contains_key(name) {
-                env.insert(name.to_string(), value);
-                return;
+    /// Переприсваивать можно только переменные, объявленные через `var mut`.
+    fn assign_var(&mut self, name: &str, value: Value) -> Result<(), RuntimeError> {
+        for env in self.visible_scopes(name).iter().rev() {
+            if let Some(binding) = env.borrow_mut().vars.get_mut(name) {
+                if !binding.mutable {
+                    return error(format!("cannot assign to immutable variable '{}'", name));
+                }
+                if !Self::value_matches_type(&value, &binding.ty) {
+                    return error(format!(
+                        "type error: variable '{}' declared as {}, but value is {:?}",
+                        name, binding.ty, value
+                    ));
+                }
+                binding.value = value;
+                return Ok(());
             }
         }
-        panic!("assignment to undeclared variable '{}'", name);
+        error(format!("assignment to undeclared variable '{}'", name))
     }
 
     /// Читаем значение переменной по имени (ищем в стеке сверху вниз).
     fn get_var(&self, name: &str) -> Option<Value> {
-        for env in self.env_stack.iter().rev() {
-            if let Some(v) = env.get(name) {
-                return Some(v.clone());
+        for env in self.visible_scopes(name).iter().rev() {
+            if let Some(binding) = env.borrow().vars.get(name) {
+                return Some(binding.value.clone());
             }
         }
         None
     }
 
+    /// Scope, в которых ищется переменная `name` (от глобального к текущему).
+    /// Если текущий вызов объявил `global name` — только глобальный.
+    fn visible_scopes(&self, name: &str) -> &[Rc<RefCell<Scope>>] {
+        let is_global = self
+            .frame_globals
+            .last()
+            .is_some_and(|globals| globals.contains(name));
+        if is_global {
+            &self.env_stack[..1]
+        } else {
+            &self.env_stack
+        }
+    }
+
+    /// Передать программе аргументы командной строки (их вернёт `argv()`).
+    pub fn with_args(mut self, args: Vec<String>) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Подменить чтение файлов для `import` (например, исходниками в памяти).
+    #[allow(dead_code)] // main.rs читает импорты с диска
+    pub fn with_loader(mut self, loader: impl Fn(&str) -> io::Result<String> + 'static) -> Self {
+        self.loader = Box::new(loader);
+        self
+    }
+
+    /// Разрешить в условиях не-bool значения: `if xs { ... }`, `while n { ... }`
+    /// (флаг `--truthy`). Значение приводится по правилам `bool(x)`:
+    /// `0`, `""`, `[]` и т.п. — ложь. По умолчанию выключено — тогда
+    /// условие цикла обязано быть bool, а небулево условие `if` считается ложным.
+    pub fn with_truthy_conditions(mut self, enabled: bool) -> Self {
+        self.truthy_conditions = enabled;
+        self
+    }
+
+    /// Ограничить число выполнений тел циклов (`while`, `for`, `do`, `repeat`,
+    /// `loop`) за весь запуск: когда бюджет исчерпан, следующая итерация —
+    /// ошибка `iteration limit exceeded`, а не вечный цикл. Нужно, чтобы
+    /// безопасно исполнять чужой код; по умолчанию ограничения нет.
+    #[allow(dead_code)] // main.rs запускает программы без ограничения
+    pub fn with_max_iterations(mut self, limit: u64) -> Self {
+        self.max_iterations = Some(limit);
+        self
+    }
+
     /* ======================= ЗАПУСК ПРОГРАММЫ ======================= */
 
     /// Запускаем программу: сначала загружаем функции, потом исполняем
     /// глобальные операторы по порядку.
-    pub fn run(&mut self, program: &Program) {
-        // Загружаем определения функций в таблицу.
-        self.functions = program
+    ///
+    /// Возвращает `Err`, если программа упала с ошибкой, вызвала `exit`
+    /// или исчерпала бюджет итераций.
+    pub fn run(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        // Загружаем определения функций в таблицу: сначала из импортов,
+        // потом свои (они перекрывают импортированные с тем же именем).
+        self.functions = HashMap::new();
+        // бюджет итераций — на каждый запуск свой
+        self.iterations = 0;
+        self.load_imports(&program.imports, &mut HashSet::new())?;
+        for f in &program.functions {
+            self.functions.insert(f.name.clone(), Rc::new(f.clone()));
+        }
+
+        // Исполняем глобальные операторы. Если их нет, точка входа —
+        // собственная `func main()` программы без параметров (не из импорта).
+        let main = program
             .functions
             .iter()
-            .map(|f| (f.name.clone(), f.clone()))
-            .collect();
+            .find(|f| f.name == "main" && f.params.is_empty());
+        let result = match main {
+            Some(main) if program.stmts.is_empty() => {
+                self.call_function(main, Vec::new()).map(|_| ())
+            }
+            _ => match self.exec_stmts(&program.stmts) {
+                Ok(Flow::Break | Flow::Continue) => Self::loop_control_outside_loop(),
+                other => other.map(|_| ()),
+            },
+        };
+
+        // printn не заканчивает строку — досбрасываем вывод даже после ошибки.
+        let _ = self.out.flush();
+        result
+    }
 
-        // Исполняем глобальные операторы.
-        for stmt in &program.stmts {
-            let _ = self.exec_stmt(stmt);
+    /// Прочитать и распарсить импортируемые файлы и добавить их функции
+    /// в таблицу. Операторы верхнего уровня импортируемого файла не выполняются.
+    ///
+    /// `visited` — уже загруженные пути: повторный импорт того же файла
+    /// (в том числе по циклу `a -> b -> a`) просто пропускается.
+    fn load_imports(
+        &mut self,
+        imports: &[String],
+        visited: &mut HashSet<String>,
+    ) -> Result<(), RuntimeError> {
+        for path in imports {
+            if !visited.insert(path.clone()) {
+                continue;
+            }
+            let text = (self.loader)(path)
+                .map_err(|e| RuntimeError::new(format!("cannot import '{}': {}", path, e)))?;
+            let module = parser::try_parse_program(&text)
+                .map_err(|e| RuntimeError::new(format!("cannot import '{}': {}", path, e)))?;
+
+            self.load_imports(&module.imports, visited)?;
+            for f in module.functions {
+                self.functions.insert(f.name.clone(), Rc::new(f));
+            }
         }
+        Ok(())
     }
 
     /* ================== ИСПОЛНЕНИЕ ОПЕРАТОРОВ (Stmt) ================= */
 
     /// Исполнить один оператор.
     /// Возвращает:
-    ///  - Some(Value) — если встретился `return` и нужно пробросить значение наверх
-    ///  - None — обычное выполнение без выхода из функции
-    fn exec_stmt(&mut self, stmt: &Stmt) -> Option<Value> {
+    ///  - Flow::Return(Value) — если встретился `return` и нужно пробросить значение наверх
+    ///  - Flow::Break / Flow::Continue — `break` / `continue` для ближайшего цикла
+    ///  - Flow::Normal — обычное выполнение без выхода из функции
+    fn exec_stmt(&mut self, stmt: &Stmt) -> Result<Flow, RuntimeError> {
+        self.exec_stmt_inner(stmt)
+            .map_err(|err| err.at(stmt.span()))
+    }
+
+    /// Исполнение оператора без привязки ошибок к его позиции (см. `exec_stmt`).
+    fn exec_stmt_inner(&mut self, stmt: &Stmt) -> Result<Flow, RuntimeError> {
         match stmt {
             /* ----------- объявления и простые выражения ----------- */
-            Stmt::VarDecl { name, ty, init } => {
-                let value = self.eval_expr(init);
-                if !Self::value_matches_type(&value, ty) {
-                    panic!(
-                        "type error: variable '{}' declared as {:?}, but value is {:?}",
-                        name, ty, value
-                    );
+            Stmt::VarDecl {
+                name,
+                mutable,
+                ty,
+                init,
+                ..
+            } => {
+                let value = self.eval_expr(init)?;
+                // без аннотации тип переменной — это тип её начального значения
+                let ty = match ty {
+                    Some(ty) => {
+                        if !Self::value_matches_type(&value, ty) {
+                            return error(format!(
+                                "type error: variable '{}' declared as {}, but value is {:?}",
+                                name, ty, value
+                            ));
+                        }
+                        ty.clone()
+                    }
+                    None => Self::type_of(&value)?,
+                };
+                self.define_var(name.clone(), value, *mutable, ty);
+                Ok(Flow::Normal)
+            }
+
+            Stmt::VarDestructure {
+                names,
+                mutable,
+                init,
+                ..
+            } => {
+                let value = self.eval_expr(init)?;
+                for (name, value) in names.iter().zip(Self::destructure(value, names.len())?) {
+                    let ty = Self::type_of(&value)?;
+                    self.define_var(name.clone(), value, *mutable, ty);
                 }
-                self.define_var(name.clone(), value);
-                None
+                Ok(Flow::Normal)
             }
 
             Stmt::ExprStmt(expr) => {
-                let _v = self.eval_expr(expr);
-                None
+                self.eval_expr(expr)?;
+                Ok(Flow::Normal)
+            }
+
+            Stmt::Assign { name, expr, .. } => {
+                let value = self.eval_expr(expr)?;
+                self.assign_var(name, value)?;
+                Ok(Flow::Normal)
             }
 
-            Stmt::Assign { name, expr } => {
-                let value = self.eval_expr(expr);
-                self.assign_var(name, value);
-                None
+            Stmt::AssignDestructure { names, expr, .. } => {
+                let value = self.eval_expr(expr)?;
+                for (name, value) in names.iter().zip(Self::destructure(value, names.len())?) {
+                    self.assign_var(name, value)?;
+                }
+                Ok(Flow::Normal)
             }
 
             /* --------------------- return --------------------- */
             Stmt::Return(expr_opt) => {
                 let v = match expr_opt {
-                    Some(e) => self.eval_expr(e),
+                    Some(e) => self.eval_expr(e)?,
                     None => Value::Unit,
                 };
                 // сигнал "вернулись из функции"
-                Some(v)
+                Ok(Flow::Return(v))
             }
 
             /* ---------------- if / elif / else ---------------- */
@@ -154,21 +710,17 @@ impl Interpreter {
                 else_branch,
             } => {
                 // if (...)
-                if let Value::Bool(true) = self.eval_expr(cond) {
-                    if let Some(v) = self.exec_block(then_branch) {
-                        return Some(v);
-                    }
-                    return None;
+                let value = self.eval_expr(cond)?;
+                if self.condition(&value) == Some(true) {
+                    return self.exec_block(then_branch);
                 }
 
                 // elif ...
                 for branch in else_if_branches {
                     if let Stmt::ElseIfBranch { cond, then_branch } = branch {
-                        if let Value::Bool(true) = self.eval_expr(cond) {
-                            if let Some(v) = self.exec_block(then_branch) {
-                                return Some(v);
-                            }
-                            return None;
+                        let value = self.eval_expr(cond)?;
+                        if self.condition(&value) == Some(true) {
+                            return self.exec_block(then_branch);
                         }
                     } else {
                         // защитный assert — по идее такого не должно быть
@@ -177,356 +729,1447 @@ impl Interpreter {
                 }
 
                 // else ...
-                if !else_branch.is_empty() {
-                    if let Some(v) = self.exec_block(else_branch) {
-                        return Some(v);
+                self.exec_block(else_branch)
+            }
+
+            /* --------------------- match --------------------- */
+            Stmt::Match {
+                scrutinee,
+                arms,
+                default,
+            } => {
+                let value = self.eval_expr(scrutinee)?;
+
+                for (pattern, body) in arms {
+                    let pattern = self.eval_expr(pattern)?;
+                    if Self::values_equal(&value, &pattern) {
+                        return self.exec_block(body);
                     }
                 }
 
-                None
+                self.exec_block(default)
             }
 
             /* -------------------- while -------------------- */
-            Stmt::While { cond, body } => {
+            Stmt::While {
+                cond,
+                body,
+                else_branch,
+            } => {
                 loop {
-                    match self.eval_expr(cond) {
-                        Value::Bool(true) => {
-                            if let Some(v) = self.exec_block(body) {
-                                // проброс return из функции наверх
-                                return Some(v);
-                            }
-                        }
-                        Value::Bool(false) => break,
-                        _ => panic!("while condition must be bool"),
+                    let value = self.eval_expr(cond)?;
+                    match self.condition(&value) {
+                        Some(true) => match self.exec_loop_body(body)?.into() {
+                            LoopStep::Next => {}
+                            LoopStep::Break => return Ok(Flow::Normal),
+                            // проброс return из функции наверх
+                            LoopStep::Return(v) => return Ok(Flow::Return(v)),
+                        },
+                        Some(false) => break,
+                        None => return Self::condition_error("while", &value),
+                    }
+                }
+                // цикл закончился сам, без break
+                self.exec_block(else_branch)
+            }
+
+            // тело выполняется до первой проверки условия;
+            // `continue` переходит к проверке условия
+            Stmt::DoWhile { body, cond } => {
+                loop {
+                    match self.exec_loop_body(body)?.into() {
+                        LoopStep::Next => {}
+                        LoopStep::Break => break,
+                        LoopStep::Return(v) => return Ok(Flow::Return(v)),
+                    }
+                    let value = self.eval_expr(cond)?;
+                    match self.condition(&value) {
+                        Some(true) => {}
+                        Some(false) => break,
+                        None => return Self::condition_error("do-while", &value),
+                    }
+                }
+                Ok(Flow::Normal)
+            }
+
+            Stmt::Repeat { count, body } => {
+                let n = match self.eval_expr(count)? {
+                    Value::Int(n) if n >= 0 => n,
+                    other => {
+                        return error(format!(
+                            "repeat count must be a non-negative int, got {:?}",
+                            other
+                        ));
+                    }
+                };
+                for _ in 0..n {
+                    match self.exec_loop_body(body)?.into() {
+                        LoopStep::Next => {}
+                        LoopStep::Break => break,
+                        LoopStep::Return(v) => return Ok(Flow::Return(v)),
                     }
                 }
-                None
+                Ok(Flow::Normal)
             }
 
+            Stmt::Loop { body } => loop {
+                match self.exec_loop_body(body)?.into() {
+                    LoopStep::Next => {}
+                    LoopStep::Break => return Ok(Flow::Normal),
+                    LoopStep::Return(v) => return Ok(Flow::Return(v)),
+                }
+            },
+
             Stmt::For {
                 init,
                 cond,
                 step,
                 body,
+                else_branch,
             } => {
                 // отдельный scope для всего цикла:
                 // init / body / step живут в одном окружении
                 self.push_env();
-
-                // init
-                if let Some(init_stmt) = init.as_deref() {
-                    self.exec_stmt(init_stmt);
-                }
-
-                loop {
-                    // cond: если есть — проверяем, если нет — считаем true (for(;;))
-                    if let Some(cond_expr) = cond {
-                        match self.eval_expr(cond_expr) {
-                            Value::Bool(true) => {}
-                            Value::Bool(false) => break,
-                            _ => panic!("for condition must be bool"),
-                        }
-                    }
-
-                    // тело
-                    if let Some(v) = self.exec_block(body) {
-                        // проброс return из функции
-                        self.pop_env();
-                        return Some(v);
-                    }
-
-                    // step
-                    if let Some(step_stmt) = step.as_deref() {
-                        self.exec_stmt(step_stmt);
-                    }
-                }
-
+                let flow = self.exec_c_for(
+                    init.as_deref(),
+                    cond.as_ref(),
+                    step.as_deref(),
+                    body,
+                    else_branch,
+                );
                 self.pop_env();
-                None
+                flow
             }
 
             /* ---------------------- for-each ---------------------- */
             Stmt::ForEach {
                 var_name,
+                value_name,
                 iter_expr,
                 body,
+                else_branch,
             } => {
-                let iterable = self.eval_expr(iter_expr);
+                let iterable = self.eval_foreach_iterable(iter_expr)?;
 
-                match iterable {
-                    // for i in 10 { ... }  -> i = 0..9
-                    Value::Int(n) => {
-                        if n < 0 {
-                            panic!("for-each over negative int is not supported");
-                        }
-                        // отдельный scope для цикла
-                        self.push_env();
-                        for i in 0..n {
-                            self.define_var(var_name.clone(), Value::Int(i));
-                            if let Some(v) = self.exec_block(body) {
-                                self.pop_env();
-                                return Some(v);
-                            }
-                        }
-                        self.pop_env();
-                    }
+                // отдельный scope для переменной цикла
+                self.push_env();
+                let flow =
+                    self.exec_foreach(var_name, value_name.as_deref(), iterable, body, else_branch);
+                self.pop_env();
+                flow
+            }
 
-                    // for ch in "hello" { ... }
-                    Value::Str(s) => {
-                        self.push_env();
-                        for ch in s.chars() {
-                            self.define_var(var_name.clone(), Value::Str(ch.to_string()));
-                            if let Some(v) = self.exec_block(body) {
-                                self.pop_env();
-                                return Some(v);
-                            }
-                        }
-                        self.pop_env();
-                    }
+            Stmt::ForRange {
+                var_name,
+                start,
+                end,
+                step,
+                body,
+                else_branch,
+            } => {
+                let start = self.eval_range_bound("start", start)?;
+                let end = self.eval_range_bound("end", end)?;
+                let step = match step {
+                    Some(step) => self.eval_range_bound("step", step)?,
+                    None => 1,
+                };
+                if step == 0 {
+                    return error("for-range step must not be 0");
+                }
+                // при переполнении i64 диапазон просто заканчивается
+                let items = std::iter::successors(Some(start), move |i| i.checked_add(step))
+                    .take_while(move |&i| if step > 0 { i < end } else { i > end })
+                    .map(|i| (Value::Int(i), None));
 
-                    // for x in [1, 2, 3] { ... }
-                    Value::List(list) => {
-                        self.push_env();
-                        for v in list {
-                            self.define_var(var_name.clone(), v);
-                            if let Some(v) = self.exec_block(body) {
-                                self.pop_env();
-                                return Some(v);
-                            }
-                        }
-                        self.pop_env();
-                    }
+                self.push_env();
+                let flow = self.exec_loop_items(var_name, None, Box::new(items), body, else_branch);
+                self.pop_env();
+                flow
+            }
 
-                    _ => {
-                        panic!("for-each can iterate only over int, string or list");
-                    }
+            /* ------------------- try / catch ------------------- */
+            // Перехватываются только ошибки исполнения; `exit` и исчерпанный
+            // бюджет итераций проходят через `try` насквозь.
+            Stmt::Try {
+                body,
+                err_name,
+                handler,
+            } => match self.exec_block(body) {
+                // в переменную попадает только текст, без позиции
+                Err(RuntimeError::Error { msg, .. }) => {
+                    self.push_env();
+                    self.define_var(err_name.clone(), Value::Str(msg), false, Type::Str);
+                    let flow = self.exec_stmts(handler);
+                    self.pop_env();
+                    flow
                 }
+                other => other,
+            },
 
-                None
+            /* ------------------ локальная функция ------------------ */
+            Stmt::FuncDecl(func) => {
+                self.define_function(func);
+                Ok(Flow::Normal)
             }
 
-            /* ------------------ прочие / не поддержано ------------------ */
-            _ => panic!("Unsupported statement: {:?}", stmt),
-        }
-    }
+            /* ------------------------ global ------------------------ */
+            // Имена, которые функция будет читать и менять в глобальном scope.
+            Stmt::Global(names) => {
+                let Some(globals) = self.frame_globals.last_mut() else {
+                    return error("'global' can be used only inside a function");
+                };
+                let global_env = self.env_stack.first().expect("no environment");
+                for name in names {
+                    if !global_env.borrow().vars.contains_key(name) {
+                        return error(format!("global variable '{}' is not declared", name));
+                    }
+                    globals.insert(name.clone());
+                }
+                Ok(Flow::Normal)
+            }
 
-    /* =================== СООТВЕТСТВИЕ ТИПОВ / VALUE =================== */
+            /* ------------------- break / continue ------------------- */
+            Stmt::Break => Ok(Flow::Break),
+            Stmt::Continue => Ok(Flow::Continue),
 
-    /// Проверка: значение `value` подходит под статический тип `ty`?
-    fn value_matches_type(value: &Value, ty: &Type) -> bool {
-        match (value, ty) {
-            (Value::Int(_), Type::Int) => true,
-            (Value::Bool(_), Type::Bool) => true,
-            (Value::Str(_), Type::Str) => true,
-            (Value::List(_), Type::List) => true,
-            _ => false,
+            /* ------------------ прочие / не поддержано ------------------ */
+            _ => error(format!("Unsupported statement: {:?}", stmt)),
         }
     }
 
-    /* ================= ВЫЧИСЛЕНИЕ ВЫРАЖЕНИЙ (Expr) ================== */
+    /// Тело C-style `for (init; cond; step) { body }`.
+    /// Scope цикла уже создан вызывающим кодом.
+    fn exec_c_for(
+        &mut self,
+        init: Option<&Stmt>,
+        cond: Option<&Expr>,
+        step: Option<&Stmt>,
+        body: &[Stmt],
+        else_branch: &[Stmt],
+    ) -> Result<Flow, RuntimeError> {
+        // init
+        if let Some(init_stmt) = init {
+            self.exec_stmt(init_stmt)?;
+        }
 
-    fn eval_expr(&mut self, expr: &Expr) -> Value {
-        match expr {
-            Expr::Int(n) => Value::Int(*n),
-            Expr::Bool(b) => Value::Bool(*b),
-            Expr::Str(s) => Value::Str(s.clone()),
+        loop {
+            // cond: если есть — проверяем, если нет — считаем true (for(;;))
+            if let Some(cond_expr) = cond {
+                let value = self.eval_expr(cond_expr)?;
+                match self.condition(&value) {
+                    Some(true) => {}
+                    Some(false) => break,
+                    None => return Self::condition_error("for", &value),
+                }
+            }
 
-            Expr::Var(name) => self
-                .get_var(name)
-                .unwrap_or_else(|| panic!("Undefined variable {}", name)),
+            // тело; после `continue` step всё равно выполняется
+            match self.exec_loop_body(body)?.into() {
+                LoopStep::Next => {}
+                LoopStep::Break => return Ok(Flow::Normal),
+                // проброс return из функции
+                LoopStep::Return(v) => return Ok(Flow::Return(v)),
+            }
 
-            Expr::Binary { left, op, right } => {
-                let l = self.eval_expr(left);
-                let r = self.eval_expr(right);
-                self.eval_bin(l, op, r)
+            // step
+            if let Some(step_stmt) = step {
+                self.exec_stmt(step_stmt)?;
             }
+        }
 
-            Expr::ListLiteral(items) => {
-                let mut vals = Vec::new();
-                for e in items {
-                    vals.push(self.eval_expr(e));
+        self.exec_block(else_branch)
+    }
+
+    /// Тело for-each. Scope цикла уже создан вызывающим кодом.
+    fn exec_foreach(
+        &mut self,
+        var_name: &str,
+        value_name: Option<&str>,
+        iterable: Value,
+        body: &[Stmt],
+        else_branch: &[Stmt],
+    ) -> Result<Flow, RuntimeError> {
+        if value_name.is_some() && !matches!(iterable, Value::Dict(_)) {
+            return error(format!(
+                "for-each with two variables can iterate only over a dict, got {:?}",
+                iterable
+            ));
+        }
+
+        // раскладываем итерируемое значение в последовательность элементов;
+        // у словаря элемент — ключ, а второй частью пары идёт его значение
+        // (числа и списки обходятся лениво, без промежуточного Vec)
+        let items: Box<dyn Iterator<Item = (Value, Option<Value>)>> = match iterable {
+            // for i in 10 { ... }  -> i = 0..9
+            Value::Int(n) => {
+                if n < 0 {
+                    return error("for-each over negative int is not supported");
                 }
-                Value::List(vals)
+                Box::new((0..n).map(|i| (Value::Int(i), None)))
             }
 
-            Expr::Call { callee, args } => self.eval_call(callee, args),
-        }
+            // for ch in "hello" { ... }  -> ch — char
+            Value::Str(s) => Box::new(
+                s.chars()
+                    .map(|ch| (Value::Char(ch), None))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+
+            // for x in [1, 2, 3] { ... } / for x in (1, "a") { ... }
+            Value::List(list) | Value::Tuple(list) => {
+                Box::new((0..list.len()).map(move |i| (list[i].clone(), None)))
+            }
+
+            // for k in d { ... } / for k, v in d { ... } — в порядке вставки
+            Value::Dict(dict) => Box::new(
+                dict.iter()
+                    .map(|(key, value)| (key.clone(), Some(value.clone())))
+                    .collect::<Vec<_>>()
+                    .into_iter(),
+            ),
+
+            _ => return error("for-each can iterate only over int, string, list, tuple or dict"),
+        };
+
+        self.exec_loop_items(var_name, value_name, items, body, else_branch)
     }
 
-    /* ================== ВЫЗОВЫ ФУНКЦИЙ (BUILTIN/USER) ================= */
+    /// Общий цикл for-each и for-range: для каждого элемента связать
+    /// переменные цикла и выполнить тело.
+    fn exec_loop_items(
+        &mut self,
+        var_name: &str,
+        value_name: Option<&str>,
+        items: Box<dyn Iterator<Item = (Value, Option<Value>)>>,
+        body: &[Stmt],
+        else_branch: &[Stmt],
+    ) -> Result<Flow, RuntimeError> {
+        for (item, value) in items {
+            let ty = Self::type_of(&item)?;
+            self.define_var(var_name.to_string(), item, false, ty);
+            if let (Some(name), Some(value)) = (value_name, value) {
+                let ty = Self::type_of(&value)?;
+                self.define_var(name.to_string(), value, false, ty);
+            }
+            match self.exec_loop_body(body)?.into() {
+                LoopStep::Next => {}
+                LoopStep::Break => return Ok(Flow::Normal),
+                LoopStep::Return(v) => return Ok(Flow::Return(v)),
+            }
+        }
 
-    /// Вызов функции (сначала пробуем stdlib, потом пользовательские).
-    fn eval_call(&mut self, callee: &String, args: &Vec<Expr>) -> Value {
-        let value_args: Vec<Value> = args.iter().map(|expr| self.eval_expr(expr)).collect();
+        self.exec_block(else_branch)
+    }
 
-        // 1) встроенные функции (stdlib)
-        if let Some(result) = stdlib::call_builtin(&callee, &value_args) {
-            return result;
+    /// Одно выполнение тела цикла; сначала тратит итерацию из бюджета
+    /// (см. `with_max_iterations`).
+    fn exec_loop_body(&mut self, body: &[Stmt]) -> Result<Flow, RuntimeError> {
+        if let Some(limit) = self.max_iterations
+            && self.iterations >= limit
+        {
+            return Err(RuntimeError::IterationLimit(limit));
         }
+        self.iterations += 1;
+        self.exec_block(body)
+    }
 
-        // 2) пользовательские функции
-        if let Some(func) = self.functions.get(callee).cloned() {
-            return self.call_function(&func, value_args);
+    /// Граница или шаг `for i in a..b step k` — обязательно int.
+    fn eval_range_bound(&mut self, what: &str, expr: &Expr) -> Result<i64, RuntimeError> {
+        match self.eval_expr(expr)? {
+            Value::Int(n) => Ok(n),
+            other => error(format!(
+                "for-range {} must be int, got {}",
+                what,
+                stdlib::describe_value(&other)
+            )),
         }
+    }
 
-        panic!("Unknown function '{}'", callee);
+    /// Вычислить то, по чему идёт for-each. `for i in range(n)` не строит
+    /// список из `n` элементов: цикл идёт по числу `n`, как `for i in n`.
+    /// Только для встроенного `range` — если программа его переопределила,
+    /// вызывается её функция.
+    fn eval_foreach_iterable(&mut self, iter_expr: &Expr) -> Result<Value, RuntimeError> {
+        if let Expr::Call { callee, args, .. } = iter_expr
+            && let Expr::Var { name, .. } = callee.as_ref()
+            && name == "range"
+            && !matches!(self.get_var(name), Some(Value::Func(_)))
+            && self.find_function(name).is_none()
+        {
+            let mut value_args = Vec::with_capacity(args.len());
+            for expr in args {
+                value_args.push(self.eval_expr(expr)?);
+            }
+            return Ok(Value::Int(stdlib::range_len(&value_args)?));
+        }
+        self.eval_expr(iter_expr)
+    }
+
+    /// Разложить список или кортеж ровно на `count` значений (для `var a, b = ...`).
+    fn destructure(value: Value, count: usize) -> Result<Vec<Value>, RuntimeError> {
+        let items = match &value {
+            Value::List(items) | Value::Tuple(items) => items,
+            other => {
+                return error(format!(
+                    "cannot destructure {:?}: expected a list or tuple",
+                    other
+                ));
+            }
+        };
+        if items.len() != count {
+            return error(format!(
+                "cannot destructure {} values into {} variables",
+                items.len(),
+                count
+            ));
+        }
+        Ok(items.to_vec())
+    }
+
+    /* =================== СООТВЕТСТВИЕ ТИПОВ / VALUE =================== */
+
+    /// Проверка: значение `value` подходит под статический тип `ty`?
+    /// Для `list[T]` проверяется каждый элемент.
+    /// Статический аналог — `checker::compatible`; правила меняются вместе.
+    fn value_matches_type(value: &Value, ty: &Type) -> bool {
+        match (value, ty) {
+            (Value::Int(_), Type::Int) => true,
+            (Value::Float(_), Type::Float) => true,
+            (Value::Bool(_), Type::Bool) => true,
+            (Value::Str(_), Type::Str) => true,
+            (Value::Char(_), Type::Char) => true,
+            (Value::List(_), Type::List(None)) => true,
+            (Value::List(items), Type::List(Some(elem))) => items
+                .iter()
+                .all(|item| Self::value_matches_type(item, elem)),
+            (Value::Dict(_), Type::Dict) => true,
+            (Value::Tuple(_), Type::Tuple) => true,
+            (Value::Func(_), Type::Func) => true,
+            _ => false,
+        }
+    }
+
+    /// Структурное равенство значений (см. `impl PartialEq for Value`). Значения разных типов просто не равны
+    /// (в отличие от `==`, где это ошибка типов).
+    fn values_equal(a: &Value, b: &Value) -> bool {
+        a == b
+    }
+
+    /// Статический тип, выводимый из значения (для `var x = ...`).
+    /// Для списков тип элементов не выводится — получается просто `list`.
+    fn type_of(value: &Value) -> Result<Type, RuntimeError> {
+        match value {
+            Value::Int(_) => Ok(Type::Int),
+            Value::Float(_) => Ok(Type::Float),
+            Value::Bool(_) => Ok(Type::Bool),
+            Value::Str(_) => Ok(Type::Str),
+            Value::Char(_) => Ok(Type::Char),
+            Value::List(_) => Ok(Type::List(None)),
+            Value::Dict(_) => Ok(Type::Dict),
+            Value::Tuple(_) => Ok(Type::Tuple),
+            Value::Func(_) => Ok(Type::Func),
+            Value::Unit => error("type error: cannot use a unit value as a variable"),
+        }
+    }
+
+    /* ================= ВЫЧИСЛЕНИЕ ВЫРАЖЕНИЙ (Expr) ================== */
+
+    /// Вычислить выражение; ошибка привязывается к позиции выражения
+    /// (если её ещё не привязал какой-нибудь из подвыражений).
+    fn eval_expr(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        self.eval_expr_inner(expr)
+            .map_err(|err| err.at(expr.span()))
+    }
+
+    fn eval_expr_inner(&mut self, expr: &Expr) -> Result<Value, RuntimeError> {
+        match expr {
+            Expr::Int(n) => Ok(Value::Int(*n)),
+            Expr::Float(x) => Ok(Value::Float(*x)),
+            Expr::Bool(b) => Ok(Value::Bool(*b)),
+            Expr::Str(s) => Ok(Value::Str(s.clone())),
+            Expr::Char(c) => Ok(Value::Char(*c)),
+
+            // имя функции без вызова — это функция-значение
+            Expr::Var { name, .. } => match self.get_var(name) {
+                Some(value) => Ok(value),
+                None => match self.find_function(name) {
+                    Some(func) => Ok(Value::Func(Rc::new(Closure { func, env: None }))),
+                    None => error(format!("Undefined variable {}", name)),
+                },
+            },
+
+            Expr::Lambda(func) => Ok(Value::Func(Rc::new(Closure {
+                func: Rc::clone(func),
+                env: Some(self.env_stack.clone()),
+            }))),
+
+            // && и || вычисляют правую часть только при необходимости
+            Expr::Binary {
+                left,
+                op: op @ (BinOp::And | BinOp::Or),
+                right,
+                ..
+            } => self.eval_logical(left, op, right),
+
+            Expr::Binary {
+                left, op, right, ..
+            } => {
+                let l = self.eval_expr(left)?;
+                let r = self.eval_expr(right)?;
+                Self::eval_bin(l, op, r)
+            }
+
+            Expr::CompareChain { first, rest, .. } => {
+                let mut left = self.eval_expr(first)?;
+                for (op, expr) in rest {
+                    let right = self.eval_expr(expr)?;
+                    match Self::eval_bin(left, op, right.clone())? {
+                        Value::Bool(true) => left = right,
+                        // дальше не считаем, как у обычного &&
+                        _ => return Ok(Value::Bool(false)),
+                    }
+                }
+                Ok(Value::Bool(true))
+            }
+
+            Expr::Unary { op, expr, .. } => {
+                let v = self.eval_expr(expr)?;
+                Self::eval_unary(op, v)
+            }
+
+            Expr::Index { target, index, .. } => {
+                let target = self.eval_expr(target)?;
+                let index = self.eval_expr(index)?;
+                self.eval_index(target, index)
+            }
+
+            Expr::ListLiteral(items) => {
+                let mut vals = Vec::new();
+                for e in items {
+                    vals.push(self.eval_expr(e)?);
+                }
+                Ok(Value::list(vals))
+            }
+
+            Expr::TupleLiteral(items) => {
+                let mut vals = Vec::with_capacity(items.len());
+                for e in items {
+                    vals.push(self.eval_expr(e)?);
+                }
+                Ok(Value::Tuple(Rc::new(vals)))
+            }
+
+            Expr::DictLiteral(pairs) => {
+                let mut dict = Dict::new();
+                for (key, value) in pairs {
+                    let key = self.eval_expr(key)?;
+                    let value = self.eval_expr(value)?;
+                    dict.insert(key, value)?;
+                }
+                Ok(Value::Dict(Rc::new(dict)))
+            }
+
+            Expr::Call { callee, args, .. } => match callee.as_ref() {
+                Expr::Var { name, .. } => self.eval_call(name, args),
+                other => {
+                    // вызов результата выражения: `f(1)(2)`, `xs[0](x)`
+                    let callee = self.eval_expr(other)?;
+                    let mut value_args = Vec::with_capacity(args.len());
+                    for expr in args {
+                        value_args.push(self.eval_expr(expr)?);
+                    }
+                    self.call_value(&callee, value_args)
+                }
+            },
+        }
+    }
+
+    /// `xs[i]` для списков и кортежей, `s[i]` для строк (результат — char, как в `for ch in s`).
+    /// Отрицательный индекс считается с конца: `xs[-1]` — последний элемент.
+    /// `d[key]` для словарей — значение по ключу (нет ключа — ошибка).
+    fn eval_index(&self, target: Value, index: Value) -> Result<Value, RuntimeError> {
+        if let Value::Dict(dict) = &target {
+            return match dict.get(&index)? {
+                Some(value) => Ok(value.clone()),
+                None => error(format!("key {:?} not found in dict", index)),
+            };
+        }
+
+        let i = match index {
+            Value::Int(i) => i,
+            other => return error(format!("index must be int, got {:?}", other)),
+        };
+
+        match target {
+            Value::Tuple(items) => {
+                let len = items.len();
+                stdlib::resolve_index(i, len)
+                    .and_then(|i| items.get(i).cloned())
+                    .ok_or_else(|| {
+                        RuntimeError::new(format!(
+                            "index {} out of range for tuple of length {}",
+                            i, len
+                        ))
+                    })
+            }
+            Value::List(items) => {
+                let len = items.len();
+                stdlib::resolve_index(i, len)
+                    .and_then(|i| items.get(i).cloned())
+                    .ok_or_else(|| {
+                        RuntimeError::new(format!(
+                            "index {} out of range for list of length {}",
+                            i, len
+                        ))
+                    })
+            }
+            Value::Str(s) => {
+                let len = s.chars().count();
+                stdlib::resolve_index(i, len)
+                    .and_then(|i| s.chars().nth(i))
+                    .map(Value::Char)
+                    .ok_or_else(|| {
+                        RuntimeError::new(format!(
+                            "index {} out of range for string of length {}",
+                            i, len
+                        ))
+                    })
+            }
+            other => error(format!("cannot index into {:?}", other)),
+        }
+    }
+
+    /* ================== ВЫЗОВЫ ФУНКЦИЙ (BUILTIN/USER) ================= */
+
+    /// Вызов функции (сначала пробуем stdlib, потом пользовательские).
+    fn eval_call(&mut self, callee: &str, args: &[Expr]) -> Result<Value, RuntimeError> {
+        let mut value_args: Vec<Value> = Vec::with_capacity(args.len());
+        for expr in args {
+            value_args.push(self.eval_expr(expr)?);
+        }
+
+        // Функции программы перекрывают встроенные с тем же именем:
+        // `func len(...)` или `var len = fn(...) { ... }` заменяют builtin `len`.
+        // Переменная с нефункциональным значением (`var len = 3`) на вызовы не влияет.
+
+        // 1) переменная, в которой лежит функция: `var f = fn(...) { ... }`
+        if let Some(value @ Value::Func(_)) = self.get_var(callee) {
+            return self.call_value(&value, value_args);
+        }
+
+        // 2) пользовательские функции
+        if let Some(func) = self.find_function(callee) {
+            return self.call_function(&func, value_args);
+        }
+
+        // 3) встроенные функции, которым нужно состояние интерпретатора
+        if let Some(result) = self.call_stateful_builtin(callee, &value_args)? {
+            return Ok(result);
+        }
+
+        // 4) встроенные функции (stdlib)
+        if let Some(result) = stdlib::call_builtin(callee, &value_args, self.out.as_mut())? {
+            return Ok(result);
+        }
+
+        match self.suggest_function(callee) {
+            Some(similar) => error(format!(
+                "Unknown function '{}'; did you mean '{}'?",
+                callee, similar
+            )),
+            None => error(format!("Unknown function '{}'", callee)),
+        }
+    }
+
+    /// Самое похожее на `name` известное имя функции (встроенной или
+    /// пользовательской) — если оно отличается не больше чем на пару символов.
+    fn suggest_function(&self, name: &str) -> Option<String> {
+        let local = self
+            .env_stack
+            .iter()
+            .flat_map(|env| env.borrow().functions.keys().cloned().collect::<Vec<_>>());
+        let candidates = stdlib::BUILTIN_NAMES
+            .iter()
+            .chain(STATEFUL_BUILTIN_NAMES)
+            .map(|s| s.to_string())
+            .chain(self.functions.keys().cloned())
+            .chain(local);
+
+        // короткие имена подсказываем только при одной опечатке
+        let max_distance = if name.chars().count() <= 3 { 1 } else { 2 };
+        candidates
+            .map(|candidate| (edit_distance(name, &candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min()
+            .map(|(_, candidate)| candidate)
+    }
+
+    /// Встроенные функции, которые читают или меняют состояние интерпретатора,
+    /// поэтому не могут жить в stateless `stdlib::call_builtin`.
+    /// Возвращает Ok(None), если имя не из этого набора.
+    fn call_stateful_builtin(
+        &mut self,
+        name: &str,
+        args: &[Value],
+    ) -> Result<Option<Value>, RuntimeError> {
+        let result = match name {
+            // clock() — миллисекунды с момента запуска интерпретатора.
+            // Само значение ничего не значит, смысл имеет только разность двух замеров.
+            "clock" => {
+                if !args.is_empty() {
+                    return error("clock() expects no arguments");
+                }
+                Value::Int(self.started.elapsed().as_millis() as i64)
+            }
+
+            // argv() — аргументы командной строки программы, список строк.
+            "argv" => {
+                if !args.is_empty() {
+                    return error("argv() expects no arguments");
+                }
+                Value::list(self.args.iter().cloned().map(Value::Str).collect())
+            }
+
+            // read_all() — весь оставшийся ввод одной строкой.
+            // read_lines() — он же, разбитый на строки (без `\n` и `\r\n`).
+            "read_all" | "read_lines" => {
+                if !args.is_empty() {
+                    return error(format!("{}() expects no arguments", name));
+                }
+                let mut text = String::new();
+                self.input
+                    .read_to_string(&mut text)
+                    .map_err(|e| RuntimeError::new(format!("failed to read input: {}", e)))?;
+                if name == "read_all" {
+                    Value::Str(text)
+                } else {
+                    Value::list(
+                        text.lines()
+                            .map(|line| Value::Str(line.to_string()))
+                            .collect(),
+                    )
+                }
+            }
+
+            // seed(n) — задать начальное состояние генератора,
+            // чтобы последовательность random() была воспроизводимой.
+            "seed" => {
+                let n = match args {
+                    [Value::Int(n)] => *n,
+                    _ => return error("seed(n) expects exactly 1 int argument"),
+                };
+                self.rng_state = if n == 0 { DEFAULT_SEED } else { n as u64 };
+                Value::Unit
+            }
+
+            // random(max) — псевдослучайное целое в диапазоне 0..max.
+            "random" => {
+                let max = match args {
+                    [Value::Int(max)] => *max,
+                    _ => return error("random(max) expects exactly 1 int argument"),
+                };
+                if max <= 0 {
+                    return error(format!("random(max): max must be > 0, got {}", max));
+                }
+                Value::Int((self.next_random() % max as u64) as i64)
+            }
+
+            // map(xs, f) — новый список из f(x) для каждого элемента.
+            "map" => {
+                let (items, f) = match args {
+                    [Value::List(items), f @ Value::Func(_)] => (items, f),
+                    _ => return error("map(xs, f) expects a list and a function"),
+                };
+                let mut mapped = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    mapped.push(self.call_value(f, vec![item.clone()])?);
+                }
+                Value::list(mapped)
+            }
+
+            // map2(f, a, b) — список f(a[i], b[i]); длина — по более короткому списку.
+            "map2" => {
+                let (f, a, b) = match args {
+                    [f @ Value::Func(_), Value::List(a), Value::List(b)] => (f, a, b),
+                    _ => return error("map2(f, a, b) expects a function and two lists"),
+                };
+                let mut combined = Vec::with_capacity(a.len().min(b.len()));
+                for (x, y) in a.iter().zip(b.iter()) {
+                    combined.push(self.call_value(f, vec![x.clone(), y.clone()])?);
+                }
+                Value::list(combined)
+            }
+
+            // filter(xs, pred) — элементы, для которых pred(x) вернул true.
+            "filter" => {
+                let (items, pred) = match args {
+                    [Value::List(items), pred @ Value::Func(_)] => (items, pred),
+                    _ => return error("filter(xs, pred) expects a list and a function"),
+                };
+                let mut kept = Vec::new();
+                for item in items.iter() {
+                    match self.call_value(pred, vec![item.clone()])? {
+                        Value::Bool(true) => kept.push(item.clone()),
+                        Value::Bool(false) => {}
+                        other => {
+                            return error(format!(
+                                "filter(xs, pred): pred must return bool, got {:?}",
+                                other
+                            ));
+                        }
+                    }
+                }
+                Value::list(kept)
+            }
+
+            // max_by(f, xs) / min_by(f, xs) — элемент с наибольшим / наименьшим
+            // ключом f(x). При равных ключах берётся первый такой элемент.
+            "max_by" | "min_by" => {
+                let (f, items) = match args {
+                    [f @ Value::Func(_), Value::List(items)] => (f, items),
+                    _ => return error(format!("{}(f, xs) expects a function and a list", name)),
+                };
+                let mut best: Option<(&Value, Value)> = None;
+                for item in items.iter() {
+                    let key = self.call_value(f, vec![item.clone()])?;
+                    let better = match &best {
+                        None => true,
+                        Some((_, best_key)) => {
+                            let ord = Self::compare_keys(&key, best_key)?;
+                            if name == "max_by" {
+                                ord == Ordering::Greater
+                            } else {
+                                ord == Ordering::Less
+                            }
+                        }
+                    };
+                    if better {
+                        best = Some((item, key));
+                    }
+                }
+                match best {
+                    Some((item, _)) => item.clone(),
+                    None => return error(format!("{}(f, xs): list is empty", name)),
+                }
+            }
+
+            // sort_by(f, xs) / sort_by(f, xs, descending) — новый список,
+            // упорядоченный по ключу f(x). Сортировка устойчивая: элементы
+            // с равными ключами сохраняют исходный порядок (и при descending).
+            "sort_by" => {
+                let (f, items, descending) = match args {
+                    [f @ Value::Func(_), Value::List(items)] => (f, items, false),
+                    [f @ Value::Func(_), Value::List(items), Value::Bool(desc)] => {
+                        (f, items, *desc)
+                    }
+                    _ => {
+                        return error(
+                            "sort_by(f, xs, descending?) expects a function, a list and an optional bool",
+                        );
+                    }
+                };
+                let mut keyed = Vec::with_capacity(items.len());
+                for item in items.iter() {
+                    keyed.push((self.call_value(f, vec![item.clone()])?, item.clone()));
+                }
+                let mut failure = None;
+                keyed.sort_by(|(a, _), (b, _)| {
+                    let ord = if descending {
+                        Self::compare_keys(b, a)
+                    } else {
+                        Self::compare_keys(a, b)
+                    };
+                    ord.unwrap_or_else(|e| {
+                        failure.get_or_insert(e);
+                        Ordering::Equal
+                    })
+                });
+                if let Some(e) = failure {
+                    return Err(e);
+                }
+                Value::list(keyed.into_iter().map(|(_, item)| item).collect())
+            }
+
+            // group_by(f, xs) — словарь: ключ f(x) -> список элементов с этим ключом.
+            // Ключи идут в порядке первого появления, элементы в группе — в исходном порядке.
+            "group_by" => {
+                let (f, items) = match args {
+                    [f @ Value::Func(_), Value::List(items)] => (f, items),
+                    _ => return error("group_by(f, xs) expects a function and a list"),
+                };
+                let mut groups: Vec<(Value, Vec<Value>)> = Vec::new();
+                let mut positions: HashMap<DictKey, usize> = HashMap::new();
+                for item in items.iter() {
+                    let key = self.call_value(f, vec![item.clone()])?;
+                    let pos = *positions
+                        .entry(DictKey::from_value(&key)?)
+                        .or_insert_with(|| {
+                            groups.push((key, Vec::new()));
+                            groups.len() - 1
+                        });
+                    groups[pos].1.push(item.clone());
+                }
+                let mut dict = Dict::new();
+                for (key, group) in groups {
+                    dict.insert(key, Value::list(group))?;
+                }
+                Value::Dict(Rc::new(dict))
+            }
+
+            // bench(f, n) — вызвать функцию без аргументов n раз и вернуть
+            // [total_ms, per_call_us]: общее время в миллисекундах и среднее
+            // на один вызов в микросекундах (оба — float). Функцию можно
+            // передать значением (`bench(work, 100)`) или именем (`bench("work", 100)`).
+            "bench" => {
+                let (f, iterations) = match args {
+                    [f @ (Value::Func(_) | Value::Str(_)), Value::Int(n)] => (f, *n),
+                    _ => return error("bench(f, iterations) expects a function and an int"),
+                };
+                if iterations <= 0 {
+                    return error(format!(
+                        "bench(f, iterations): iterations must be > 0, got {}",
+                        iterations
+                    ));
+                }
+                let f = self.function_arg("bench", f)?;
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    self.call_value(&f, Vec::new())?;
+                }
+                let elapsed = start.elapsed();
+                Value::list(vec![
+                    Value::Float(elapsed.as_secs_f64() * 1e3),
+                    Value::Float(elapsed.as_secs_f64() * 1e6 / iterations as f64),
+                ])
+            }
+
+            // dispatch(table, key, args) — вызвать функцию, записанную в словаре
+            // `table` под ключом `key`, с аргументами из списка `args`:
+            //
+            //   var commands = {"add": "add", "neg": "negate"}
+            //   dispatch(commands, cmd, [1, 2])
+            //
+            // Значение в словаре — имя функции (как в `bench`) или сама функция.
+            "dispatch" => {
+                let (table, key, call_args) = match args {
+                    [Value::Dict(table), key, Value::List(call_args)] => (table, key, call_args),
+                    _ => {
+                        return error(
+                            "dispatch(table, key, args) expects a dict, a key and a list of arguments",
+                        );
+                    }
+                };
+                let f = match table.get(key)? {
+                    Some(f @ (Value::Func(_) | Value::Str(_))) => f.clone(),
+                    Some(other) => {
+                        return error(format!(
+                            "dispatch: entry for key {} must be a function or a function name, got {}",
+                            stdlib::describe_value(key),
+                            stdlib::describe_value(other)
+                        ));
+                    }
+                    None => {
+                        return error(format!(
+                            "dispatch: key {} not found in table",
+                            stdlib::describe_value(key)
+                        ));
+                    }
+                };
+                let f = self.function_arg("dispatch", &f)?;
+                self.call_value(&f, call_args.to_vec())?
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+
+    /// Функция-аргумент встроенной функции `who`: либо само значение-функция,
+    /// либо имя пользовательской функции строкой (`bench("work", 10)`).
+    fn function_arg(&self, who: &str, f: &Value) -> Result<Value, RuntimeError> {
+        match f {
+            Value::Str(name) => match self.find_function(name) {
+                Some(func) => Ok(Value::Func(Rc::new(Closure { func, env: None }))),
+                None => error(format!("{}: unknown function '{}'", who, name)),
+            },
+            other => Ok(other.clone()),
+        }
+    }
+
+    /// Сравнение ключей для `max_by` / `min_by` / `sort_by` (см. `stdlib::compare_keys`).
+    fn compare_keys(a: &Value, b: &Value) -> Result<Ordering, RuntimeError> {
+        stdlib::compare_keys(a, b)
+            .ok_or_else(|| RuntimeError::new(format!("cannot compare keys {:?} and {:?}", a, b)))
+    }
+
+    /// Следующее значение xorshift64.
+    fn next_random(&mut self) -> u64 {
+        let mut x = self.rng_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.rng_state = x;
+        x
+    }
+
+    /// Вызов функции-значения. Лямбда исполняется поверх захваченных
+    /// окружений; стек вызывающего кода на это время откладывается.
+    fn call_value(&mut self, callee: &Value, args: Vec<Value>) -> Result<Value, RuntimeError> {
+        let closure = match callee {
+            Value::Func(closure) => closure,
+            other => return error(format!("value {:?} is not callable", other)),
+        };
+        match &closure.env {
+            Some(env) => {
+                let caller_env = mem::replace(&mut self.env_stack, env.clone());
+                let result = self.call_function(&closure.func, args);
+                self.env_stack = caller_env;
+                result
+            }
+            None => self.call_function(&closure.func, args),
+        }
     }
 
     /// Вызов пользовательской функции.
-    fn call_function(&mut self, func: &Function, args: Vec<Value>) -> Value {
+    fn call_function(&mut self, func: &Function, args: Vec<Value>) -> Result<Value, RuntimeError> {
         if func.params.len() != args.len() {
-            panic!(
+            return error(format!(
                 "function '{}' expected {} arguments, got {}",
                 func.name,
                 func.params.len(),
                 args.len()
-            );
+            ));
         }
 
-        // создаём новый scope для параметров (и локальных переменных функции)
-        let mut locals = HashMap::new();
-        for ((param_name, _param_type), arg_val) in func.params.iter().zip(args.into_iter()) {
-            locals.insert(param_name.clone(), arg_val);
+        // создаём новый scope для параметров (и локальных переменных функции);
+        // параметры неизменяемы, как и обычные `var`
+        let mut locals = Scope::default();
+        for ((param_name, param_type), arg_val) in func.params.iter().zip(args) {
+            if !Self::value_matches_type(&arg_val, param_type) {
+                return error(format!(
+                    "type error: parameter '{}' of function '{}' declared as {}, but value is {:?}",
+                    param_name, func.name, param_type, arg_val
+                ));
+            }
+            locals.vars.insert(
+                param_name.clone(),
+                Binding {
+                    value: arg_val,
+                    mutable: false,
+                    ty: param_type.clone(),
+                },
+            );
         }
-        self.env_stack.push(locals);
+        self.env_stack.push(Rc::new(RefCell::new(locals)));
+        self.frame_globals.push(HashSet::new());
 
         // выполняем тело
-        let mut ret = Value::Unit;
-        for stmt in &func.body {
-            if let Some(v) = self.exec_stmt(stmt) {
-                ret = v;
-                break;
-            }
-        }
+        let result = self.exec_body(&func.body);
 
         // выходим из функции — убираем её scope
+        self.frame_globals.pop();
         self.pop_env();
 
-        ret
+        let value = result?;
+        if let Some(ret_type) = &func.ret_type
+            && !Self::value_matches_type(&value, ret_type)
+        {
+            return error(format!(
+                "type error: function '{}' declared to return {}, but returned {:?}",
+                func.name, ret_type, value
+            ));
+        }
+        Ok(value)
+    }
+
+    /// Тело функции. Если последний оператор — выражение (и до него
+    /// не случился `return`), его значение и есть результат функции.
+    /// Другие операторы (`if`, циклы, ...) так не работают: тело,
+    /// закончившееся ими без `return`, возвращает `Unit`.
+    fn exec_body(&mut self, body: &[Stmt]) -> Result<Value, RuntimeError> {
+        let (init, tail) = match body.split_last() {
+            Some((Stmt::ExprStmt(expr), init)) => (init, Some(expr)),
+            _ => (body, None),
+        };
+        match self.exec_stmts(init)? {
+            Flow::Return(v) => Ok(v),
+            Flow::Normal => match tail {
+                Some(expr) => self.eval_expr(expr),
+                None => Ok(Value::Unit),
+            },
+            Flow::Break | Flow::Continue => Self::loop_control_outside_loop(),
+        }
     }
 
     /* ================= БИНАРНЫЕ ОПЕРАЦИИ (BinOp) ================= */
 
-    fn eval_bin(&self, left: Value, op: &BinOp, right: Value) -> Value {
-        match op {
+    /// Не зависит от состояния интерпретатора — этим же кодом
+    /// оптимизатор сворачивает константы, так что результат свёртки
+    /// всегда совпадает с тем, что получилось бы при исполнении.
+    /// Типы результатов повторяет `Checker::binary_type` — правила меняются вместе.
+    pub(crate) fn eval_bin(left: Value, op: &BinOp, right: Value) -> Result<Value, RuntimeError> {
+        // Арифметика с float: если хотя бы один операнд float, int приводится к float.
+        // Деление на 0.0 не ошибка, а inf / NaN, как в IEEE 754.
+        if let BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div = op
+            && let Some((l, r)) = Self::float_operands(&left, &right)
+        {
+            let x = match op {
+                BinOp::Add => l + r,
+                BinOp::Sub => l - r,
+                BinOp::Mul => l * r,
+                _ => l / r,
+            };
+            return Ok(Value::Float(x));
+        }
+
+        // Сравнение с float — тоже по правилам IEEE 754 (int приводится к float):
+        // NaN не равен ничему, даже себе, поэтому с NaN `!=` — true,
+        // а `==`, `<`, `<=`, `>`, `>=` — false.
+        if let BinOp::Eq | BinOp::NotEq | BinOp::Lt | BinOp::LtEq | BinOp::Gt | BinOp::GtEq = op
+            && let Some((l, r)) = Self::float_operands(&left, &right)
+        {
+            let result = match op {
+                BinOp::Eq => l == r,
+                BinOp::NotEq => l != r,
+                BinOp::Lt => l < r,
+                BinOp::LtEq => l <= r,
+                BinOp::Gt => l > r,
+                _ => l >= r,
+            };
+            return Ok(Value::Bool(result));
+        }
+
+        let value = match op {
             BinOp::Add => match (left, right) {
-                (Value::Int(left), Value::Int(right)) => Value::Int(left + right),
-                (Value::Str(left), Value::Str(right)) => Value::Str(left + &right),
-                _ => panic!("Type error in '+'"),
+                (Value::Int(left), Value::Int(right)) => {
+                    Value::Int(Self::checked_int(left.checked_add(right), "+")?)
+                }
+                // операнды уже свои (не клоны), поэтому дописываем в буфер
+                // левой строки, а не собираем новую
+                (Value::Str(mut left), Value::Str(right)) => {
+                    left.push_str(&right);
+                    Value::Str(left)
+                }
+                // символы склеиваются в строку: 'a' + 'b' == "ab"
+                (Value::Str(mut left), Value::Char(right)) => {
+                    left.push(right);
+                    Value::Str(left)
+                }
+                (Value::Char(left), Value::Str(mut right)) => {
+                    right.insert(0, left);
+                    Value::Str(right)
+                }
+                (Value::Char(left), Value::Char(right)) => Value::Str(format!("{left}{right}")),
+                (left, right) => return Self::operand_error("+", "add", &left, &right),
             },
 
             BinOp::Sub => match (left, right) {
-                (Value::Int(left), Value::Int(right)) => Value::Int(left - right),
-                _ => panic!("Type error, you can't subtract non-int values"),
+                (Value::Int(left), Value::Int(right)) => {
+                    Value::Int(Self::checked_int(left.checked_sub(right), "-")?)
+                }
+                (left, right) => return Self::operand_error("-", "subtract", &left, &right),
             },
 
             BinOp::Div => match (left, right) {
-                (Value::Int(left), Value::Int(right)) => Value::Int(left / right),
-                _ => panic!("Type error, you can't divide non-int values"),
+                (Value::Int(_), Value::Int(0)) => return error("division by zero"),
+                (Value::Int(left), Value::Int(right)) => {
+                    Value::Int(Self::checked_int(left.checked_div(right), "/")?)
+                }
+                (left, right) => return Self::operand_error("/", "divide", &left, &right),
             },
 
             BinOp::Mul => match (left, right) {
-                (Value::Int(left), Value::Int(right)) => Value::Int(left * right),
-                _ => panic!("Type error, you can't multiply non-int values"),
+                (Value::Int(left), Value::Int(right)) => {
+                    Value::Int(Self::checked_int(left.checked_mul(right), "*")?)
+                }
+                (left, right) => return Self::operand_error("*", "multiply", &left, &right),
             },
 
-            BinOp::Eq => match (left, right) {
-                (Value::Int(left), Value::Int(right)) => Value::Bool(left == right),
-                (Value::Bool(left), Value::Bool(right)) => Value::Bool(left == right),
-                (Value::Str(left), Value::Str(right)) => Value::Bool(left == right),
-                _ => panic!("Type error in '=='"),
-            },
+            // Сравнивать можно только значения одного вида;
+            // списки и словари сравниваются структурно.
+            BinOp::Eq => {
+                if mem::discriminant(&left) != mem::discriminant(&right) {
+                    return Self::operand_error("==", "compare", &left, &right);
+                }
+                Value::Bool(left == right)
+            }
 
             BinOp::Gt => match (left, right) {
                 (Value::Int(left), Value::Int(right)) => Value::Bool(left > right),
                 (Value::Str(left), Value::Str(right)) => Value::Bool(left.len() > right.len()),
-                _ => panic!("Type error in '>'"),
+                (Value::Char(left), Value::Char(right)) => Value::Bool(left > right),
+                (left, right) => return Self::operand_error(">", "compare", &left, &right),
             },
 
             BinOp::GtEq => match (left, right) {
                 (Value::Int(left), Value::Int(right)) => Value::Bool(left >= right),
                 (Value::Str(left), Value::Str(right)) => Value::Bool(left.len() >= right.len()),
-                _ => panic!("Type error in '>='"),
+                (Value::Char(left), Value::Char(right)) => Value::Bool(left >= right),
+                (left, right) => return Self::operand_error(">=", "compare", &left, &right),
             },
 
             BinOp::Lt => match (left, right) {
                 (Value::Int(left), Value::Int(right)) => Value::Bool(left < right),
                 (Value::Str(left), Value::Str(right)) => Value::Bool(left.len() < right.len()),
-                _ => panic!("Type error in '<'"),
+                (Value::Char(left), Value::Char(right)) => Value::Bool(left < right),
+                (left, right) => return Self::operand_error("<", "compare", &left, &right),
             },
 
             BinOp::LtEq => match (left, right) {
                 (Value::Int(left), Value::Int(right)) => Value::Bool(left <= right),
                 (Value::Str(left), Value::Str(right)) => Value::Bool(left.len() <= right.len()),
-                _ => panic!("Type error in '<='"),
+                (Value::Char(left), Value::Char(right)) => Value::Bool(left <= right),
+                (left, right) => return Self::operand_error("<=", "compare", &left, &right),
             },
 
-            BinOp::NotEq => match (left, right) {
-                (Value::Int(left), Value::Int(right)) => Value::Bool(left != right),
-                (Value::Str(left), Value::Str(right)) => Value::Bool(left != right),
-                _ => panic!("Type error in '!='"),
+            BinOp::NotEq => {
+                if mem::discriminant(&left) != mem::discriminant(&right) {
+                    return Self::operand_error("!=", "compare", &left, &right);
+                }
+                Value::Bool(left != right)
+            }
+
+            BinOp::And | BinOp::Or => {
+                unreachable!("logical operators are evaluated in eval_logical")
+            }
+        };
+        Ok(value)
+    }
+
+    /// Ошибка типов в бинарной операции: `Type error in '+': cannot add int 3 and str "x"`.
+    fn operand_error<T>(
+        op: &str,
+        verb: &str,
+        left: &Value,
+        right: &Value,
+    ) -> Result<T, RuntimeError> {
+        error(format!(
+            "Type error in '{}': cannot {} {} and {}",
+            op,
+            verb,
+            stdlib::describe_value(left),
+            stdlib::describe_value(right)
+        ))
+    }
+
+    /// Значение условия `if` / `while` / `for` / `do-while`: bool — как есть,
+    /// остальное — по правилам `bool(x)`, если включены truthy-условия.
+    /// `None` — значение не bool, а truthy-условия выключены.
+    fn condition(&self, value: &Value) -> Option<bool> {
+        match value {
+            Value::Bool(b) => Some(*b),
+            other if self.truthy_conditions => Some(stdlib::truthy(other)),
+            _ => None,
+        }
+    }
+
+    /// Условие цикла — не bool: `while condition must be bool, got int 0`.
+    fn condition_error<T>(kind: &str, value: &Value) -> Result<T, RuntimeError> {
+        error(format!(
+            "{} condition must be bool, got {}",
+            kind,
+            stdlib::describe_value(value)
+        ))
+    }
+
+    /// Пара операндов как `f64`, если хотя бы один из них float, а второй — число.
+    fn float_operands(left: &Value, right: &Value) -> Option<(f64, f64)> {
+        match (left, right) {
+            (Value::Float(l), Value::Float(r)) => Some((*l, *r)),
+            (Value::Int(l), Value::Float(r)) => Some((*l as f64, *r)),
+            (Value::Float(l), Value::Int(r)) => Some((*l, *r as f64)),
+            _ => None,
+        }
+    }
+
+    /// Результат checked-операции над `i64`: `None` означает переполнение.
+    fn checked_int(result: Option<i64>, op: &str) -> Result<i64, RuntimeError> {
+        result.ok_or_else(|| RuntimeError::new(format!("integer overflow in '{}'", op)))
+    }
+
+    /// Ленивое вычисление `&&` / `||`: правая часть считается,
+    /// только если левой недостаточно для ответа.
+    fn eval_logical(
+        &mut self,
+        left: &Expr,
+        op: &BinOp,
+        right: &Expr,
+    ) -> Result<Value, RuntimeError> {
+        let name = match op {
+            BinOp::And => "and",
+            _ => "or",
+        };
+
+        let l = match self.eval_expr(left)? {
+            Value::Bool(b) => b,
+            other => {
+                return error(format!(
+                    "Type error in '{}': expected bool, got {}",
+                    name,
+                    stdlib::describe_value(&other)
+                ));
+            }
+        };
+
+        match (op, l) {
+            (BinOp::And, false) => return Ok(Value::Bool(false)),
+            (BinOp::Or, true) => return Ok(Value::Bool(true)),
+            _ => {}
+        }
+
+        match self.eval_expr(right)? {
+            Value::Bool(r) => Ok(Value::Bool(r)),
+            other => error(format!(
+                "Type error in '{}': expected bool, got {}",
+                name,
+                stdlib::describe_value(&other)
+            )),
+        }
+    }
+
+    /* ================= УНАРНЫЕ ОПЕРАЦИИ (UnOp) ================= */
+
+    /// Как и `eval_bin`, используется оптимизатором.
+    pub(crate) fn eval_unary(op: &UnOp, value: Value) -> Result<Value, RuntimeError> {
+        match op {
+            UnOp::Not => match value {
+                Value::Bool(b) => Ok(Value::Bool(!b)),
+                other => error(format!(
+                    "Type error in 'not': expected bool, got {}",
+                    stdlib::describe_value(&other)
+                )),
+            },
+            UnOp::Neg => match value {
+                // -i64::MIN не помещается в int — это переполнение, а не заворачивание
+                Value::Int(n) => Ok(Value::Int(Self::checked_int(n.checked_neg(), "-")?)),
+                Value::Float(x) => Ok(Value::Float(-x)),
+                other => error(format!(
+                    "Type error in unary '-': expected int or float, got {}",
+                    stdlib::describe_value(&other)
+                )),
             },
         }
     }
 
     /* ===================== ВСПОМОГАТЕЛЬНОЕ: БЛОКИ ===================== */
 
-    /// Выполнить блок `{ ... }` с собственным scope.
-    /// Если внутри блока случился `return`, он пробрасывается наружу.
-    fn exec_block(&mut self, body: &[Stmt]) -> Option<Value> {
-        self.push_env();
-        let mut ret = None;
+    /// Выполнить операторы по порядку в текущем scope.
+    /// Останавливаемся на первом `return` / `break` / `continue` (или ошибке).
+    fn exec_stmts(&mut self, body: &[Stmt]) -> Result<Flow, RuntimeError> {
         for s in body {
-            if let Some(v) = self.exec_stmt(s) {
-                ret = Some(v);
-                break;
+            match self.exec_stmt(s)? {
+                Flow::Normal => {}
+                flow => return Ok(flow),
             }
         }
-        self.pop_env();
-        ret
+        Ok(Flow::Normal)
     }
-}
 
+    /// `break` / `continue` дошли до функции или до верхнего уровня программы.
+    fn loop_control_outside_loop<T>() -> Result<T, RuntimeError> {
+        error("'break' or 'continue' outside of a loop")
+    }
 
+    /// Выполнить блок `{ ... }` с собственным scope.
+    /// Если внутри блока случился `return`, он пробрасывается наружу.
+    fn exec_block(&mut self, body: &[Stmt]) -> Result<Flow, RuntimeError> {
+        self.push_env();
+        let flow = self.exec_stmts(body);
+        self.pop_env();
+        flow
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::lexer::Lexer;
     use crate::parser::Parser;
+    use std::cell::RefCell;
 
-    /// Хелпер: прогнать кусок Rusthon-кода через лексер, парсер и интерпретатор.
-    fn run_source(src: &str) {
+    fn parse_source(src: &str) -> Program {
         let lexer = Lexer::new(src);
         let mut parser = Parser::new(lexer);
-        let program = parser.parse_program();
-        let mut interp = Interpreter::new();
-        interp.run(&program);
+        parser.parse_program()
     }
 
-    #[test]
-    fn simple_arith_and_while_does_not_panic() {
-        let src = r#"
-            var x: int = 0
-            var sum: int = 0
+    /// Хелпер: прогнать кусок Rusthon-кода через лексер, парсер и интерпретатор.
+    /// Ошибка исполнения превращается в panic с её текстом,
+    /// чтобы тесты могли проверять её через `should_panic`.
+    fn run_source(src: &str) {
+        let mut interp = Interpreter::new();
+        if let Err(err) = interp.run(&parse_source(src)) {
+            panic!("{}", err);
+        }
+    }
 
-            while (x < 5) {
-                sum = sum + x
-                x = x + 1
-            }
+    /// Буфер вывода, который можно прочитать после того,
+    /// как интерпретатор забрал свою копию.
+    #[derive(Clone, Default)]
+    struct SharedOutput(Rc<RefCell<Vec<u8>>>);
 
-            print("sum =", sum)
-        "#;
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
 
-        // Если где-то баг в лексере/парсере/интерпретаторе — тест упадёт с panic.
-        run_source(src);
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Хелпер: прогнать программу и вернуть всё, что она напечатала.
+    fn run_capture(src: &str) -> String {
+        let output = SharedOutput::default();
+        let mut interp = Interpreter::new().with_output(output.clone());
+        if let Err(err) = interp.run(&parse_source(src)) {
+            panic!("{}", err);
+        }
+        String::from_utf8(output.0.borrow().clone()).expect("output is not utf-8")
+    }
+
+    /// Хелпер: прогнать программу и достать значение глобальной переменной.
+    fn run_and_get(src: &str, name: &str) -> Value {
+        let mut interp = Interpreter::new();
+        if let Err(err) = interp.run(&parse_source(src)) {
+            panic!("{}", err);
+        }
+        interp
+            .get_var(name)
+            .unwrap_or_else(|| panic!("variable '{}' is not defined", name))
+    }
+
+    /// Хелпер: текст ошибки исполнения программы (без позиции).
+    fn runtime_error(src: &str) -> String {
+        match Interpreter::new().run(&parse_source(src)) {
+            Err(RuntimeError::Error { msg, .. }) => msg,
+            other => panic!("expected a runtime error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn simple_arith_and_while_does_not_panic() {
+        let src = r#"
+            var mut x: int = 0
+            var mut sum: int = 0
+
+            while (x < 5) {
+                sum = sum + x
+                x = x + 1
+            }
+
+            print("sum =", sum)
+        "#;
+
+        // Если где-то баг в лексере/парсере/интерпретаторе — тест упадёт с panic.
+        run_source(src);
     }
 
     #[test]
     fn functions_branching_and_foreach_does_not_panic() {
         let src = r#"
             func sum_list(xs: list) {
-                var acc: int = 0
+                var mut acc: int = 0
                 for v in xs {
                     acc = acc + v
                 }
@@ -562,4 +2205,1753 @@ mod tests {
 
         run_source(src);
     }
+
+    #[test]
+    fn keyword_logical_operators() {
+        let and = run_and_get("var x: bool = true and false", "x");
+        assert!(matches!(and, Value::Bool(false)));
+
+        let or = run_and_get("var x: bool = true or false", "x");
+        assert!(matches!(or, Value::Bool(true)));
+
+        let not = run_and_get("var x: bool = not true", "x");
+        assert!(matches!(not, Value::Bool(false)));
+    }
+
+    #[test]
+    fn symbolic_and_keyword_operators_are_equivalent() {
+        let src = r#"
+            var a: bool = !(1 < 2) || 2 < 3 && true
+            var b: bool = not (1 < 2) or 2 < 3 and true
+        "#;
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(true)));
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        // правая часть не вычисляется, иначе был бы panic из-за неизвестной функции
+        let src = r#"
+            var a: bool = false and missing()
+            var b: bool = true or missing()
+        "#;
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "'and' is a reserved word")]
+    fn logical_keywords_are_reserved() {
+        run_source("var and: int = 1");
+    }
+
+    #[test]
+    fn chained_comparisons() {
+        let src = r#"
+            var a: bool = 1 < 2 < 3
+            var b: bool = 3 < 2 < 1
+            var x: int = 5
+            var c: bool = 0 <= x < 10
+            var d: bool = 1 <= 1 >= 2 != 0
+        "#;
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "c"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "d"), Value::Bool(false)));
+    }
+
+    #[test]
+    fn comparison_binds_weaker_than_addition() {
+        let src = "var a: bool = 1 < 1 + 1";
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot assign to immutable variable 'x'")]
+    fn assigning_to_immutable_var_fails() {
+        run_source(
+            r#"
+            var x: int = 0
+            x = 1
+        "#,
+        );
+    }
+
+    #[test]
+    fn assigning_to_mut_var_works() {
+        let src = r#"
+            var mut x: int = 0
+            x = x + 1
+        "#;
+        assert!(matches!(run_and_get(src, "x"), Value::Int(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot assign to immutable variable 'n'")]
+    fn function_params_are_immutable() {
+        run_source(
+            r#"
+            func f(n: int) {
+                n = 2
+            }
+            f(1)
+        "#,
+        );
+    }
+
+    #[test]
+    fn var_type_is_inferred_from_initializer() {
+        let src = r#"
+            var x = 5
+            var s = "hi"
+            var mut n = 0
+            n = n + x
+        "#;
+        assert!(matches!(run_and_get(src, "x"), Value::Int(5)));
+        assert!(matches!(run_and_get(src, "s"), Value::Str(s) if s == "hi"));
+        assert!(matches!(run_and_get(src, "n"), Value::Int(5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "type error: variable 'x' declared as int")]
+    fn annotated_type_mismatch_still_errors() {
+        run_source(r#"var x: int = "five""#);
+    }
+
+    #[test]
+    fn typed_list_accepts_matching_elements() {
+        let src = r#"
+            var xs: list[int] = [1, 2, 3]
+            var grid: list[list[int]] = [[1], [2, 3]]
+        "#;
+        assert!(matches!(run_and_get(src, "xs"), Value::List(items) if items.len() == 3));
+        assert!(matches!(run_and_get(src, "grid"), Value::List(items) if items.len() == 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "type error: variable 'xs' declared as list[int]")]
+    fn typed_list_rejects_wrong_element() {
+        run_source(r#"var xs: list[int] = [1, "a"]"#);
+    }
+
+    #[test]
+    #[should_panic(expected = "type error: variable 'xs' declared as list[int]")]
+    fn typed_list_is_checked_after_push() {
+        run_source(
+            r#"
+            var mut xs: list[int] = [1, 2]
+            xs = push(xs, "a")
+        "#,
+        );
+    }
+
+    #[test]
+    fn match_runs_first_matching_arm() {
+        let src = r#"
+            var mut out: str = ""
+            var x: int = 2
+            match x {
+                1 { out = "one" }
+                2 { out = "two" }
+                2 { out = "second two" }
+                else { out = "other" }
+            }
+        "#;
+        assert!(matches!(run_and_get(src, "out"), Value::Str(s) if s == "two"));
+    }
+
+    #[test]
+    fn match_falls_through_to_else() {
+        let src = r#"
+            var mut out: str = ""
+            match "z" {
+                "a" {
+                    out = "a"
+                }
+                else {
+                    out = "fallback"
+                }
+            }
+        "#;
+        assert!(matches!(run_and_get(src, "out"), Value::Str(s) if s == "fallback"));
+    }
+
+    #[test]
+    fn indexing_lists_and_strings() {
+        let src = r#"
+            var xs: list = [10, 20, 30]
+            var a: int = xs[1]
+            var b: char = "abc"[2]
+            var c: char = "привет"[1]
+            var same: bool = "abc"[0] == chars("abc")[0]
+        "#;
+        assert!(matches!(run_and_get(src, "a"), Value::Int(20)));
+        assert!(matches!(run_and_get(src, "b"), Value::Char('c')));
+        assert!(matches!(run_and_get(src, "c"), Value::Char('р')));
+        assert!(matches!(run_and_get(src, "same"), Value::Bool(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of range for list of length 3")]
+    fn indexing_out_of_range_fails() {
+        run_source("var x: int = [1, 2, 3][3]");
+    }
+
+    #[test]
+    fn enumerate_pairs_indices_with_values() {
+        let xs = run_and_get(r#"var xs: list = enumerate(["a", "b"])"#, "xs");
+        let expected = Value::list(vec![
+            Value::list(vec![Value::Int(0), Value::Str("a".to_string())]),
+            Value::list(vec![Value::Int(1), Value::Str("b".to_string())]),
+        ]);
+        assert!(Interpreter::values_equal(&xs, &expected));
+
+        let src = r#"
+            var mut acc: str = ""
+            for pair in enumerate(["x", "y"]) {
+                acc = acc + str(pair[0]) + pair[1]
+            }
+        "#;
+        assert!(matches!(run_and_get(src, "acc"), Value::Str(s) if s == "0x1y"));
+    }
+
+    #[test]
+    fn zip_truncates_to_shortest_list() {
+        let zipped = run_and_get(r#"var z: list = zip([1, 2, 3], ["a", "b"])"#, "z");
+        let expected = Value::list(vec![
+            Value::list(vec![Value::Int(1), Value::Str("a".to_string())]),
+            Value::list(vec![Value::Int(2), Value::Str("b".to_string())]),
+        ]);
+        assert!(Interpreter::values_equal(&zipped, &expected));
+    }
+
+    #[test]
+    fn zip_equal_length_and_more_lists() {
+        let zipped = run_and_get("var z: list = zip([1, 2], [3, 4], [5, 6])", "z");
+        let expected = Value::list(vec![
+            Value::list(vec![Value::Int(1), Value::Int(3), Value::Int(5)]),
+            Value::list(vec![Value::Int(2), Value::Int(4), Value::Int(6)]),
+        ]);
+        assert!(Interpreter::values_equal(&zipped, &expected));
+    }
+
+    #[test]
+    fn negative_indices_count_from_the_end() {
+        let src = r#"
+            var a: bool = [10, 20, 30][-1] == 30
+            var b: bool = "abc"[-1] == 'c'
+            var c: int = [10, 20, 30][-3]
+        "#;
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "c"), Value::Int(10)));
+    }
+
+    #[test]
+    #[should_panic(expected = "index -4 out of range for list of length 3")]
+    fn negative_index_out_of_range_fails() {
+        run_source("var x: int = [1, 2, 3][-4]");
+    }
+
+    #[test]
+    fn slice_supports_negative_bounds() {
+        let src = r#"
+            var xs: list = slice([1, 2, 3, 4], 1, -1)
+            var s: str = slice("hello", -3, 5)
+        "#;
+        let expected = Value::list(vec![Value::Int(2), Value::Int(3)]);
+        assert!(Interpreter::values_equal(
+            &run_and_get(src, "xs"),
+            &expected
+        ));
+        assert!(matches!(run_and_get(src, "s"), Value::Str(s) if s == "llo"));
+    }
+
+    #[test]
+    fn unary_minus() {
+        let src = "var x: int = 2 * -3 - -1";
+        assert!(matches!(run_and_get(src, "x"), Value::Int(-5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow in '*'")]
+    fn multiplication_overflow_is_reported() {
+        run_source("var x: int = 9223372036854775807 * 2");
+    }
+
+    #[test]
+    #[should_panic(expected = "integer overflow in '+'")]
+    fn accumulating_overflow_is_reported() {
+        run_source(
+            r#"
+            var mut sum: int = 9223372036854775800
+            for i in 10 {
+                sum = sum + i
+            }
+        "#,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "division by zero")]
+    fn division_by_zero_is_reported() {
+        run_source("var x: int = 1 / 0");
+    }
+
+    #[test]
+    fn exit_stops_execution_with_code() {
+        let src = r#"
+            var mut x: int = 1
+            exit(2)
+            x = 5
+        "#;
+        let mut interp = Interpreter::new();
+        assert_eq!(interp.run(&parse_source(src)), Err(RuntimeError::Exit(2)));
+        assert!(matches!(interp.get_var("x"), Some(Value::Int(1))));
+    }
+
+    #[test]
+    fn exit_unwinds_from_nested_calls() {
+        let src = r#"
+            func inner() {
+                for i in 10 {
+                    exit()
+                }
+            }
+            inner()
+            print("unreachable")
+        "#;
+        let mut interp = Interpreter::new();
+        assert_eq!(interp.run(&parse_source(src)), Err(RuntimeError::Exit(0)));
+    }
+
+    #[test]
+    fn runtime_errors_are_returned_not_panicked() {
+        let mut interp = Interpreter::new();
+        let result = interp.run(&parse_source("print(missing)"));
+        assert_eq!(
+            result,
+            Err(RuntimeError::new("Undefined variable missing".to_string())
+                .at(Some(Span { line: 1 })))
+        );
+    }
+
+    #[test]
+    fn clock_is_monotonic() {
+        let src = "var a = clock()\nvar b = clock()\nvar ok = b >= a";
+        assert!(matches!(run_and_get(src, "ok"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "a"), Value::Int(n) if n >= 0));
+    }
+
+    #[test]
+    fn seeded_random_is_deterministic() {
+        let src =
+            "seed(42)\nvar xs = [random(100), random(100), random(100), random(100), random(100)]";
+        let expected = Value::list(vec![
+            Value::Int(74),
+            Value::Int(71),
+            Value::Int(54),
+            Value::Int(36),
+            Value::Int(62),
+        ]);
+        assert!(Interpreter::values_equal(
+            &run_and_get(src, "xs"),
+            &expected
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "random(max): max must be > 0")]
+    fn random_rejects_non_positive_max() {
+        run_source("random(0)");
+    }
+
+    #[test]
+    fn print_variants_write_to_captured_output() {
+        assert_eq!(run_capture("print(1, \"a\", [1, 2])"), "1 a [1, 2]\n");
+        assert_eq!(run_capture("printn(1)\nprintn(2)"), "12");
+        assert_eq!(run_capture("print_sep(\", \", \";\", 1, 2, 3)"), "1, 2, 3;");
+    }
+
+    #[test]
+    fn eprintln_goes_to_stderr_not_output() {
+        let src = "eprintln(\"warning:\", [1, 2])\neprint(1)\nprint(\"ok\")";
+        assert_eq!(run_capture(src), "ok\n");
+
+        let mut out = Vec::new();
+        let result = stdlib::call_builtin("eprintln", &[Value::Int(1)], &mut out);
+        assert!(matches!(result, Ok(Some(Value::Unit))));
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn summing_a_large_list_reads_it_without_copying() {
+        // Каждое чтение `xs` внутри цикла раньше копировало весь список.
+        let src = "var xs = range(20000)\nvar mut total = 0\nvar mut i = 0\nwhile (i < len(xs)) {\n    total = total + xs[i]\n    i = i + 1\n}";
+        assert!(matches!(run_and_get(src, "total"), Value::Int(199990000)));
+    }
+
+    #[test]
+    fn int_keyed_dict_lookup() {
+        let src = "var d = {1: \"one\", 2: \"two\", [1, 2]: \"pair\"}\nvar a = d[2]\nvar b = d[[1, 2]]\nvar n = len(d)";
+        assert!(matches!(run_and_get(src, "a"), Value::Str(s) if s == "two"));
+        assert!(matches!(run_and_get(src, "b"), Value::Str(s) if s == "pair"));
+        assert!(matches!(run_and_get(src, "n"), Value::Int(3)));
+        assert_eq!(
+            run_capture("print({1: \"a\", \"k\": [1, 2]})"),
+            "{1: \"a\", \"k\": [1, 2]}\n"
+        );
+    }
+
+    #[test]
+    fn equal_lists_hash_equally() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+
+        let hash = |v: &Value| {
+            let mut h = DefaultHasher::new();
+            DictKey::from_value(v).unwrap().hash(&mut h);
+            h.finish()
+        };
+        let a = Value::list(vec![Value::Int(1), Value::Str("x".to_string())]);
+        let b = Value::list(vec![Value::Int(1), Value::Str("x".to_string())]);
+        assert_eq!(a, b);
+        assert_eq!(hash(&a), hash(&b));
+        assert!(DictKey::from_value(&Value::Unit).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot be a dict key")]
+    fn dict_rejects_unhashable_keys() {
+        run_source("var d = {{}: 1}");
+    }
+
+    #[test]
+    fn equality_is_shared_by_eq_contains_and_index_of() {
+        let src = "var eq = [1, [2]] == [1, [2]] and {1: 2} == {1: 2}\nvar c = contains([[1], [2]], [2]) and contains({\"k\": 1}, \"k\") and contains(\"hello\", \"ell\")\nvar i = index_of([5, 6, 7], 7)\nvar j = index_of([5], 1)";
+        assert!(matches!(run_and_get(src, "eq"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "c"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "i"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "j"), Value::Int(-1)));
+    }
+
+    /// Поиск в списке с `else` у цикла: `found` остаётся -1, только если `else` выполнился.
+    fn search_src(target: i64) -> String {
+        format!(
+            "var mut found = 0\nfor x in [3, 5, 7] {{\n    if (x == {}) {{\n        found = x\n        break\n    }}\n}} else {{\n    found = -1\n}}",
+            target
+        )
+    }
+
+    #[test]
+    fn loop_else_runs_only_without_break() {
+        assert!(matches!(
+            run_and_get(&search_src(5), "found"),
+            Value::Int(5)
+        ));
+        assert!(matches!(
+            run_and_get(&search_src(4), "found"),
+            Value::Int(-1)
+        ));
+
+        let src = "var mut n = 0\nwhile (n < 3) {\n    n = n + 1\n}\nelse {\n    n = n * 10\n}";
+        assert!(matches!(run_and_get(src, "n"), Value::Int(30)));
+    }
+
+    #[test]
+    fn break_and_continue_in_loops() {
+        let src = "var mut total = 0\nfor (var mut i = 0; i < 10; i = i + 1) {\n    if (i == 6) {\n        break\n    }\n    if (i == 2) {\n        continue\n    }\n    total = total + i\n}";
+        // 0 + 1 + 3 + 4 + 5
+        assert!(matches!(run_and_get(src, "total"), Value::Int(13)));
+    }
+
+    #[test]
+    #[should_panic(expected = "'break' or 'continue' outside of a loop")]
+    fn break_outside_loop_is_an_error() {
+        run_source("func f() {\n    break\n}\nfor x in [1] {\n    f()\n}");
+    }
+
+    #[test]
+    fn postfix_calls_and_indexing_on_any_expression() {
+        let src = "func pair() {\n    return [\"a\", \"b\"]\n}\nvar x = range(5)[2]\nvar y = pair()[1]\nvar z = head(tail([1, 2, 3]))\nvar w = [[1, 2], [3]][0][1]";
+        assert!(matches!(run_and_get(src, "x"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "y"), Value::Str(s) if s == "b"));
+        assert!(matches!(run_and_get(src, "z"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "w"), Value::Int(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "is not callable")]
+    fn calling_a_non_function_value_is_an_error() {
+        run_source("var xs = [1, 2]\nxs[0](1)");
+    }
+
+    #[test]
+    fn do_while_runs_body_at_least_once() {
+        let src = "var mut n = 0\ndo {\n    n = n + 1\n} while (n > 100)";
+        assert!(matches!(run_and_get(src, "n"), Value::Int(1)));
+
+        let src = "var mut n = 0\ndo {\n    n = n + 1\n    if (n == 2) {\n        continue\n    }\n    if (n == 4) {\n        break\n    }\n} while (n < 10)";
+        assert!(matches!(run_and_get(src, "n"), Value::Int(4)));
+    }
+
+    #[test]
+    #[should_panic(expected = "do-while condition must be bool")]
+    fn do_while_condition_must_be_bool() {
+        run_source("do {\n    print(1)\n} while (1)");
+    }
+
+    #[test]
+    fn int_or_and_float_or_fall_back_on_bad_input() {
+        let src = "var a = int_or(\"42\", 0)\nvar b = int_or(\"oops\", -1)\nvar c = float_or(\"2.5\", 0.0)\nvar d = float_or(\"x\", -1.0)";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(42)));
+        assert!(matches!(run_and_get(src, "b"), Value::Int(-1)));
+        assert!(matches!(run_and_get(src, "c"), Value::Float(x) if x == 2.5));
+        assert!(matches!(run_and_get(src, "d"), Value::Float(x) if x == -1.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot parse string \"oops\" as integer")]
+    fn strict_int_still_errors() {
+        run_source("int(\"oops\")");
+    }
+
+    #[test]
+    fn float_arithmetic_promotes_ints() {
+        let src =
+            "var a: float = 1.5 * 2\nvar b = 7 / 2.0\nvar c = -float(\"0.25\")\nvar d = int(3.9)";
+        assert!(matches!(run_and_get(src, "a"), Value::Float(x) if x == 3.0));
+        assert!(matches!(run_and_get(src, "b"), Value::Float(x) if x == 3.5));
+        assert!(matches!(run_and_get(src, "c"), Value::Float(x) if x == -0.25));
+        assert!(matches!(run_and_get(src, "d"), Value::Int(3)));
+        assert_eq!(run_capture("print(1.5 + 1.5, [0.5])"), "3.0 [0.5]\n");
+    }
+
+    #[test]
+    fn first_and_last_with_default() {
+        let src = "var a = first([1, 2], 0)\nvar b = first([], -1)\nvar c = last([1, 2], 0)\nvar d = last([], \"none\")";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "b"), Value::Int(-1)));
+        assert!(matches!(run_and_get(src, "c"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "d"), Value::Str(s) if s == "none"));
+    }
+
+    const NESTED_FUNC_SRC: &str = "func outer(x: int) {\n    func double(n: int) {\n        return n * 2\n    }\n    return double(x) + 1\n}\nvar r = outer(5)";
+
+    #[test]
+    fn nested_function_is_callable_inside_parent() {
+        assert!(matches!(run_and_get(NESTED_FUNC_SRC, "r"), Value::Int(11)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown function 'double'")]
+    fn nested_function_is_not_visible_outside() {
+        run_source(&format!("{}\ndouble(1)", NESTED_FUNC_SRC));
+    }
+
+    /// Загрузчик `import` для тестов: файлы — строки в памяти.
+    fn memory_loader(files: &[(&str, &str)]) -> impl Fn(&str) -> io::Result<String> + 'static {
+        let files: HashMap<String, String> = files
+            .iter()
+            .map(|(path, text)| (path.to_string(), text.to_string()))
+            .collect();
+        move |path| {
+            files
+                .get(path)
+                .cloned()
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such file"))
+        }
+    }
+
+    #[test]
+    fn import_merges_functions_from_another_source() {
+        let loader = memory_loader(&[
+            (
+                "math.rht",
+                "import \"main.rht\"\nfunc square(n: int) {\n    return n * n\n}",
+            ),
+            ("main.rht", "import \"math.rht\""),
+        ]);
+        // main.rht и math.rht импортируют друг друга — цикл не зацикливает загрузку
+        let src = "import \"math.rht\"\nimport \"math.rht\"\nvar r = square(7)";
+        let mut interp = Interpreter::new().with_loader(loader);
+        interp.run(&parse_source(src)).unwrap();
+        assert!(matches!(interp.get_var("r"), Some(Value::Int(49))));
+    }
+
+    #[test]
+    fn import_of_missing_file_is_an_error() {
+        let mut interp = Interpreter::new().with_loader(memory_loader(&[]));
+        let err = interp
+            .run(&parse_source("import \"nope.rht\""))
+            .unwrap_err();
+        assert_eq!(
+            err,
+            RuntimeError::new("cannot import 'nope.rht': no such file")
+        );
+    }
+
+    #[test]
+    fn import_with_parse_error_is_an_error() {
+        let loader = memory_loader(&[("broken.rht", "func f( {\n}")]);
+        let mut interp = Interpreter::new().with_loader(loader);
+        let err = interp
+            .run(&parse_source("import \"broken.rht\""))
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("cannot import 'broken.rht': Parse error near token"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn argv_returns_preset_args() {
+        let mut interp = Interpreter::new().with_args(vec!["foo".to_string(), "bar".to_string()]);
+        interp.run(&parse_source("var a = argv()")).unwrap();
+        let expected = Value::list(vec![
+            Value::Str("foo".to_string()),
+            Value::Str("bar".to_string()),
+        ]);
+        assert_eq!(interp.get_var("a"), Some(expected));
+        assert!(
+            matches!(run_and_get("var a = argv()", "a"), Value::List(items) if items.is_empty())
+        );
+    }
+
+    #[test]
+    fn clamp_bounds_ints_and_floats() {
+        let src = "var a = clamp(5, 0, 10)\nvar b = clamp(-3, 0, 10)\nvar c = clamp(99, 0, 10)\nvar d = clamp(1.5, 0, 1)";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(5)));
+        assert!(matches!(run_and_get(src, "b"), Value::Int(0)));
+        assert!(matches!(run_and_get(src, "c"), Value::Int(10)));
+        assert!(matches!(run_and_get(src, "d"), Value::Float(x) if x == 1.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "lo 10 is greater than hi 0")]
+    fn clamp_rejects_inverted_bounds() {
+        run_source("clamp(5, 10, 0)");
+    }
+
+    #[test]
+    fn empty_and_comment_only_sources_are_empty_programs() {
+        for src in ["", "\n\n", "# only a comment\n// another one\n\n#"] {
+            let program = parse_source(src);
+            assert!(program.imports.is_empty());
+            assert!(program.functions.is_empty());
+            assert!(program.stmts.is_empty());
+            assert_eq!(run_capture(src), "");
+        }
+    }
+
+    #[test]
+    fn is_empty_checks_collections() {
+        let src = "var a = is_empty([])\nvar b = is_empty(\"\")\nvar c = is_empty([1])\nvar d = is_empty({})\nvar e = is_empty(())\nvar f = is_empty((1,))";
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "c"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "d"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "e"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "f"), Value::Bool(false)));
+    }
+
+    #[test]
+    #[should_panic(expected = "is_empty(...) is not defined")]
+    fn is_empty_rejects_numbers() {
+        run_source("is_empty(0)");
+    }
+
+    #[test]
+    fn repeat_runs_body_exactly_n_times() {
+        let src = "var mut n = 0\nrepeat 3 {\n    n = n + 1\n}\nrepeat 0 {\n    n = 100\n}";
+        assert!(matches!(run_and_get(src, "n"), Value::Int(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "repeat count must be a non-negative int")]
+    fn repeat_rejects_negative_count() {
+        run_source("repeat -1 {\n}");
+    }
+
+    #[test]
+    fn loop_exits_on_break() {
+        let src =
+            "var mut i = 0\nloop {\n    i = i + 1\n    if (i == 5) {\n        break\n    }\n}";
+        assert!(matches!(run_and_get(src, "i"), Value::Int(5)));
+    }
+
+    #[test]
+    fn inline_lambda_is_callable() {
+        let src = "var double = fn(x: int) { return x * 2 }\nvar a = double(21)\nvar b = fn(s: str) { return s + \"!\" }(\"hi\")";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(42)));
+        assert!(matches!(run_and_get(src, "b"), Value::Str(s) if s == "hi!"));
+    }
+
+    #[test]
+    fn lambda_captures_outer_variables_by_reference() {
+        let src = "var mut k = 10
+func make_adder(n: int) {
+    return fn(x: int) { return x + n + k }
+}
+var add5 = make_adder(5)
+k = 100
+var r = add5(1)";
+        assert!(matches!(run_and_get(src, "r"), Value::Int(106)));
+    }
+
+    #[test]
+    fn named_function_is_a_value() {
+        let src = "func inc(x: int) {\n    return x + 1\n}\nvar f: func = inc\nvar fs = [f]\nvar r = fs[0](1)";
+        assert!(matches!(run_and_get(src, "r"), Value::Int(2)));
+    }
+
+    #[test]
+    fn map_and_filter_take_lambdas() {
+        let src = "var xs = map([1, 2, 3], fn(x: int) { return x * 10 })\nvar ys = filter(xs, fn(x: int) { return x > 10 })";
+        assert_eq!(
+            run_and_get(src, "ys"),
+            Value::list(vec![Value::Int(20), Value::Int(30)])
+        );
+    }
+
+    #[test]
+    fn char_literals_and_string_iteration() {
+        let src = "var c: char = 'a'\nvar mut cs: list[char] = []\nfor ch in \"hi\" {\n    cs = push(cs, ch)\n}\nvar s = 'o' + 'k' + \"!\"";
+        assert!(matches!(run_and_get(src, "c"), Value::Char('a')));
+        assert_eq!(
+            run_and_get(src, "cs"),
+            Value::list(vec![Value::Char('h'), Value::Char('i')])
+        );
+        assert!(matches!(run_and_get(src, "s"), Value::Str(s) if s == "ok!"));
+    }
+
+    #[test]
+    fn ord_and_chr_convert_chars() {
+        let src = "var a = ord('A')\nvar b = chr(98)\nvar c = 'a' < 'b'";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(65)));
+        assert!(matches!(run_and_get(src, "b"), Value::Char('b')));
+        assert!(matches!(run_and_get(src, "c"), Value::Bool(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Type error in '=='")]
+    fn char_and_str_are_different_types() {
+        run_source("var x = 'a' == \"a\"");
+    }
+
+    #[test]
+    fn max_by_and_min_by_use_the_key_function() {
+        let src = "func length(s: str) {
+    return len(s)
+}
+var words = [\"a\", \"abc\", \"ab\", \"xyz\"]
+var longest = max_by(length, words)
+var shortest = min_by(length, words)";
+        assert!(matches!(run_and_get(src, "longest"), Value::Str(s) if s == "abc"));
+        assert!(matches!(run_and_get(src, "shortest"), Value::Str(s) if s == "a"));
+    }
+
+    #[test]
+    #[should_panic(expected = "max_by(f, xs): list is empty")]
+    fn max_by_on_empty_list_is_an_error() {
+        run_source("max_by(fn(x: int) { return x }, [])");
+    }
+
+    #[test]
+    fn read_lines_splits_input_into_strings() {
+        let mut interp = Interpreter::new().with_input("alpha\nbeta\r\n\ngamma\n".as_bytes());
+        let program = parse_source("var lines = read_lines()\nvar rest = read_all()");
+        interp.run(&program).expect("program failed");
+        assert_eq!(
+            interp.get_var("lines"),
+            Some(Value::list(
+                ["alpha", "beta", "", "gamma"]
+                    .iter()
+                    .map(|s| Value::Str(s.to_string()))
+                    .collect()
+            ))
+        );
+        // весь ввод уже прочитан
+        assert_eq!(interp.get_var("rest"), Some(Value::Str(String::new())));
+    }
+
+    #[test]
+    fn try_catches_raise_and_binds_the_message() {
+        let src = "var mut log = \"\"
+try {
+    log = log + \"a\"
+    raise(\"boom\")
+    log = log + \"never\"
+} catch e {
+    log = log + \"|\" + e
+}";
+        assert!(matches!(run_and_get(src, "log"), Value::Str(s) if s == "a|boom"));
+    }
+
+    #[test]
+    fn try_catches_runtime_errors_from_called_functions() {
+        let src = "func div(a: int, b: int) {\n    return a / b\n}\nvar mut r = \"\"\ntry {\n    div(1, 0)\n} catch err {\n    r = err\n}\nvar after = div(4, 2)";
+        assert!(matches!(run_and_get(src, "r"), Value::Str(s) if s == "division by zero"));
+        assert!(matches!(run_and_get(src, "after"), Value::Int(2)));
+    }
+
+    #[test]
+    fn exit_is_not_caught_by_try() {
+        let program = parse_source("try {\n    exit(3)\n} catch e {\n    print(e)\n}");
+        let result = Interpreter::new().run(&program);
+        assert_eq!(result, Err(RuntimeError::Exit(3)));
+    }
+
+    #[test]
+    fn for_each_over_dict_visits_keys_in_insertion_order() {
+        let src = "var d = {\"b\": 2, \"a\": 1, \"c\": 3}\nvar mut ks = \"\"\nfor k in d {\n    ks = ks + k\n}";
+        assert!(matches!(run_and_get(src, "ks"), Value::Str(s) if s == "bac"));
+    }
+
+    #[test]
+    fn for_each_key_value_sums_dict_values() {
+        let src = "var d = {\"x\": 10, \"y\": 20, \"z\": 12}\nvar mut total = 0\nvar mut names = \"\"\nfor k, v in d {\n    total = total + v\n    names = names + k\n}";
+        assert!(matches!(run_and_get(src, "total"), Value::Int(42)));
+        assert!(matches!(run_and_get(src, "names"), Value::Str(s) if s == "xyz"));
+    }
+
+    #[test]
+    #[should_panic(expected = "two variables can iterate only over a dict")]
+    fn for_each_key_value_over_list_is_an_error() {
+        run_source("for a, b in [1, 2] {\n}");
+    }
+
+    #[test]
+    fn for_each_over_large_range_does_not_build_a_list() {
+        // список из миллиона значений — десятки мегабайт; ленивый обход их не занимает
+        let src = "var mut total = 0\nfor i in range(1000000) {\n    total = total + i\n}";
+        let start = std::time::Instant::now();
+        assert!(matches!(
+            run_and_get(src, "total"),
+            Value::Int(499999500000)
+        ));
+        assert!(start.elapsed() < std::time::Duration::from_secs(30));
+    }
+
+    #[test]
+    fn range_outside_for_each_is_still_a_list() {
+        let src = "var r = range(4)\nvar n = len(r)\nvar third = r[2]\nvar has = contains(r, 3)";
+        assert!(matches!(run_and_get(src, "n"), Value::Int(4)));
+        assert!(matches!(run_and_get(src, "third"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "has"), Value::Bool(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "range(n): n must be >= 0")]
+    fn for_each_over_negative_range_reports_range_error() {
+        run_source("for i in range(-1) {\n}");
+    }
+
+    #[test]
+    fn global_lets_a_function_update_a_top_level_counter() {
+        // без `global` вызов из `run` менял бы локальную `counter` функции `run`
+        let src = "var mut counter = 0
+func bump() {
+    global counter
+    counter = counter + 1
+}
+func run() {
+    var mut counter = 100
+    bump()
+    bump()
+}
+run()
+bump()";
+        assert!(matches!(run_and_get(src, "counter"), Value::Int(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "global variable 'missing' is not declared")]
+    fn global_of_undeclared_variable_is_an_error() {
+        run_source("func f() {\n    global missing\n}\nf()");
+    }
+
+    #[test]
+    #[should_panic(expected = "'global' can be used only inside a function")]
+    fn global_at_top_level_is_an_error() {
+        run_source("var mut x = 1\nglobal x");
+    }
+
+    #[test]
+    fn bench_calls_the_function_and_reports_timings() {
+        let src = "var mut calls = 0
+func work() {
+    calls = calls + 1
+}
+var by_value = bench(work, 50)
+var by_name = bench(\"work\", 25)";
+        assert!(matches!(run_and_get(src, "calls"), Value::Int(75)));
+        for name in ["by_value", "by_name"] {
+            match run_and_get(src, name) {
+                Value::List(timings) => match timings.as_slice() {
+                    [Value::Float(total_ms), Value::Float(per_call_us)] => {
+                        assert!(*total_ms >= 0.0 && *per_call_us >= 0.0);
+                        // среднее на вызов (мкс) не больше общего времени (мс → мкс)
+                        assert!(*per_call_us <= *total_ms * 1e3);
+                    }
+                    other => panic!("expected [total_ms, per_call_us], got {:?}", other),
+                },
+                other => panic!("expected a list, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "iterations must be > 0")]
+    fn bench_with_zero_iterations_is_an_error() {
+        run_source("func work() {\n}\nbench(work, 0)");
+    }
+
+    #[test]
+    fn float_comparisons_promote_ints_and_treat_nan_as_unordered() {
+        let src = "var lt = 1.5 < 2.0
+var mixed_eq = 3.0 == 3
+var mixed_ge = 2 >= 2.5
+var nan = 0.0 / 0.0
+var nan_eq = nan == nan
+var nan_ne = nan != nan
+var nan_lt = nan < 1.0
+var nan_ge = nan >= 1.0";
+        for (name, expected) in [
+            ("lt", true),
+            ("mixed_eq", true),
+            ("mixed_ge", false),
+            ("nan_eq", false),
+            ("nan_ne", true),
+            ("nan_lt", false),
+            ("nan_ge", false),
+        ] {
+            assert!(
+                matches!(run_and_get(src, name), Value::Bool(b) if b == expected),
+                "{} should be {}",
+                name,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn pad_helpers_align_by_char_count() {
+        let src = "var right = pad_left(\"5\", 3)
+var left = pad_right(\"ab\", 4)
+var mid = center(\"ab\", 5)
+var zeros = pad_left(\"7\", 3, '0')
+var wide = pad_right(\"щи\", 4, \"-\")
+var long = pad_left(\"hello\", 2)";
+        for (name, expected) in [
+            ("right", "  5"),
+            ("left", "ab  "),
+            ("mid", " ab  "),
+            ("zeros", "007"),
+            ("wide", "щи--"),
+            ("long", "hello"),
+        ] {
+            assert!(
+                matches!(run_and_get(src, name), Value::Str(s) if s == expected),
+                "{} should be {:?}",
+                name,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn join_lines_and_format_table_build_text() {
+        let src = "var joined = join_lines([\"a\", \"b\"])
+var table = format_table([[\"name\", \"qty\"], [\"apple\", 3], [\"fig\", 12, true]])";
+        assert!(matches!(run_and_get(src, "joined"), Value::Str(s) if s == "a\nb"));
+        assert!(matches!(
+            run_and_get(src, "table"),
+            Value::Str(s) if s == "name   qty\napple  3\nfig    12   true"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "lines must be str")]
+    fn join_lines_rejects_non_strings() {
+        run_source("join_lines([\"a\", 1])");
+    }
+
+    #[test]
+    fn long_elif_chain_with_blank_lines_parses() {
+        let mut src =
+            String::from("var x = 250\nvar mut hit = -1\nif (x == 0) {\n    hit = 0\n}\n");
+        for i in 1..300 {
+            src.push_str(&format!("\n\nelif (x == {i}) {{\n    hit = {i}\n}}\n"));
+        }
+        src.push_str("\n\nelse {\n    hit = 1000\n}\nvar mut y = 0\ny = hit");
+        assert!(matches!(run_and_get(&src, "y"), Value::Int(250)));
+    }
+
+    #[test]
+    fn get_with_default_and_set_build_a_word_count() {
+        let src = "var d = {\"a\": 1}
+var missing = get(d, \"missing\", 0)
+var present = get(d, \"a\", 0)
+var mut counts = {}
+for w in [\"to\", \"be\", \"or\", \"not\", \"to\", \"be\"] {
+    counts = set(counts, w, get(counts, w, 0) + 1)
+}
+var to = get(counts, \"to\", 0)
+var not_count = get(counts, \"not\", 0)
+var size = len(counts)
+var has_be = contains_key(counts, \"be\")
+var original = len(d)
+var d2 = set(d, \"b\", 2)";
+        assert!(matches!(run_and_get(src, "missing"), Value::Int(0)));
+        assert!(matches!(run_and_get(src, "present"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "to"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "not_count"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "size"), Value::Int(4)));
+        assert!(matches!(run_and_get(src, "has_be"), Value::Bool(true)));
+        // set не меняет исходный словарь
+        assert!(matches!(run_and_get(src, "original"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "d2"), Value::Dict(d) if d.len() == 2));
+    }
+
+    #[test]
+    fn group_by_buckets_elements_by_key() {
+        let src = "func parity(n: int) {
+    if (n / 2 * 2 == n) {
+        return \"even\"
+    }
+    return \"odd\"
+}
+var groups = group_by(parity, [1, 2, 3, 4, 5])
+var odd = groups[\"odd\"]
+var even = groups[\"even\"]
+var keys = to_list(groups)";
+        assert!(
+            matches!(run_and_get(src, "odd"), Value::List(xs) if *xs == vec![Value::Int(1), Value::Int(3), Value::Int(5)])
+        );
+        assert!(
+            matches!(run_and_get(src, "even"), Value::List(xs) if *xs == vec![Value::Int(2), Value::Int(4)])
+        );
+        // ключи — в порядке первого появления
+        assert!(
+            matches!(run_and_get(src, "keys"), Value::List(ks) if *ks == vec![Value::Str("odd".into()), Value::Str("even".into())])
+        );
+    }
+
+    #[test]
+    fn type_errors_show_values_and_their_types() {
+        assert_eq!(
+            runtime_error("var x = 3 + \"x\""),
+            "Type error in '+': cannot add int 3 and str \"x\""
+        );
+        assert_eq!(
+            runtime_error("var x = [1, 2] * 2"),
+            "Type error in '*': cannot multiply list [1, 2] and int 2"
+        );
+        assert_eq!(
+            runtime_error("var x = 'a' == \"a\""),
+            "Type error in '==': cannot compare char 'a' and str \"a\""
+        );
+        assert_eq!(
+            runtime_error("while (0) {\n}"),
+            "while condition must be bool, got int 0"
+        );
+        assert_eq!(
+            runtime_error("var x = not \"yes\""),
+            "Type error in 'not': expected bool, got str \"yes\""
+        );
+    }
+
+    #[test]
+    fn words_splits_on_runs_of_whitespace() {
+        let src = "var ws = words(\"  a   b \\tc \")
+var lines = split_whitespace(\"x\\ny\")
+var none = words(\"   \")";
+        let strs = |xs: &[&str]| {
+            xs.iter()
+                .map(|s| Value::Str(s.to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert!(matches!(run_and_get(src, "ws"), Value::List(xs) if *xs == strs(&["a", "b", "c"])));
+        assert!(matches!(run_and_get(src, "lines"), Value::List(xs) if *xs == strs(&["x", "y"])));
+        assert!(matches!(run_and_get(src, "none"), Value::List(xs) if xs.is_empty()));
+    }
+
+    #[test]
+    fn assert_eq_passes_on_equal_values_and_shows_both_sides_otherwise() {
+        run_source("assert_eq([1, 2], [1, 2])\nassert_eq({\"a\": 1}, {\"a\": 1}, \"dicts\")");
+        assert_eq!(
+            runtime_error("assert_eq([1, 2], [1, 3])"),
+            "assertion failed: left = [1, 2], right = [1, 3]"
+        );
+        assert_eq!(
+            runtime_error("assert_eq(\"a\", 'a', \"kinds\")"),
+            "assertion failed: kinds: left = \"a\", right = 'a'"
+        );
+    }
+
+    #[test]
+    fn for_range_with_and_without_step() {
+        let src = "var mut plain = []
+for i in 0..5 {
+    plain = push(plain, i)
+}
+var mut even = []
+for i in 0..10 step 2 {
+    even = push(even, i)
+}
+var mut down = []
+var step = 3
+for i in 10..0 step -step {
+    down = push(down, i)
+}
+var mut empty = 0
+for i in 5..5 {
+    empty = empty + 1
+}";
+        let ints = |xs: &[i64]| xs.iter().map(|&n| Value::Int(n)).collect::<Vec<_>>();
+        assert!(
+            matches!(run_and_get(src, "plain"), Value::List(xs) if *xs == ints(&[0, 1, 2, 3, 4]))
+        );
+        assert!(
+            matches!(run_and_get(src, "even"), Value::List(xs) if *xs == ints(&[0, 2, 4, 6, 8]))
+        );
+        assert!(matches!(run_and_get(src, "down"), Value::List(xs) if *xs == ints(&[10, 7, 4, 1])));
+        assert!(matches!(run_and_get(src, "empty"), Value::Int(0)));
+    }
+
+    #[test]
+    fn for_range_errors() {
+        assert_eq!(
+            runtime_error("for i in 0..3 step 0 {\n}"),
+            "for-range step must not be 0"
+        );
+        assert_eq!(
+            runtime_error("for i in 0..\"3\" {\n}"),
+            "for-range end must be int, got str \"3\""
+        );
+    }
+
+    #[test]
+    fn main_is_called_when_there_are_no_top_level_statements() {
+        let out = run_capture("func main() {\n    print(\"from main\")\n}");
+        assert_eq!(out, "from main\n");
+    }
+
+    #[test]
+    fn top_level_statements_take_precedence_over_main() {
+        let out = run_capture("func main() {\n    print(\"from main\")\n}\nprint(\"top\")");
+        assert_eq!(out, "top\n");
+    }
+
+    #[test]
+    fn int_boundaries_are_reported_not_wrapped() {
+        assert_eq!(
+            runtime_error("var x = int(\"99999999999999999999\")"),
+            "int(x): integer out of range: \"99999999999999999999\""
+        );
+        assert_eq!(
+            runtime_error("var x = int(\"12abc\")"),
+            "int(x): cannot parse string \"12abc\" as integer"
+        );
+        let min = "var min = -9223372036854775807 - 1\n";
+        assert_eq!(
+            runtime_error(&format!("{min}var x = -min")),
+            "integer overflow in '-'"
+        );
+        assert_eq!(
+            runtime_error(&format!("{min}var x = abs(min)")),
+            "integer overflow in 'abs'"
+        );
+        assert!(matches!(run_and_get("var x = abs(-5)", "x"), Value::Int(5)));
+        assert!(matches!(
+            run_and_get("var x = int_or(\"99999999999999999999\", -1)", "x"),
+            Value::Int(-1)
+        ));
+    }
+
+    #[test]
+    fn map2_combines_lists_elementwise() {
+        let src = "func add(a: int, b: int) {
+    return a + b
+}
+var sums = map2(add, [1, 2, 3], [10, 20, 30])
+var short = map2(fn(a: int, b: str) { return b }, [1, 2, 3], [\"x\"])";
+        let ints = |xs: &[i64]| xs.iter().map(|&n| Value::Int(n)).collect::<Vec<_>>();
+        assert!(matches!(run_and_get(src, "sums"), Value::List(xs) if *xs == ints(&[11, 22, 33])));
+        assert!(
+            matches!(run_and_get(src, "short"), Value::List(xs) if *xs == vec![Value::Str("x".into())])
+        );
+    }
+
+    #[test]
+    fn parse_int_and_parse_float_report_success() {
+        let src = "var good = parse_int(\"42\")
+var bad = parse_int(\"x\")
+var huge = parse_int(\"99999999999999999999\")
+var half = parse_float(\"0.5\")
+var nope = parse_float(\"half\")";
+        let pair = |ok: bool, v: Value| vec![Value::Bool(ok), v];
+        assert!(
+            matches!(run_and_get(src, "good"), Value::List(xs) if *xs == pair(true, Value::Int(42)))
+        );
+        assert!(
+            matches!(run_and_get(src, "bad"), Value::List(xs) if *xs == pair(false, Value::Int(0)))
+        );
+        assert!(
+            matches!(run_and_get(src, "huge"), Value::List(xs) if *xs == pair(false, Value::Int(0)))
+        );
+        assert!(
+            matches!(run_and_get(src, "half"), Value::List(xs) if *xs == pair(true, Value::Float(0.5)))
+        );
+        assert!(
+            matches!(run_and_get(src, "nope"), Value::List(xs) if *xs == pair(false, Value::Float(0.0)))
+        );
+    }
+
+    #[test]
+    fn user_functions_shadow_builtins() {
+        let src = "func double(x: int) {
+    return x * 2
+}
+func len(x: list) {
+    return 100
+}
+var d = double(21)
+var n = len([1])";
+        assert!(matches!(run_and_get(src, "d"), Value::Int(42)));
+        assert!(matches!(run_and_get(src, "n"), Value::Int(100)));
+    }
+
+    #[test]
+    fn function_variables_shadow_builtins_but_plain_values_do_not() {
+        let src = "var first_n = len([1, 2])
+func run() {
+    var len = fn(x: list) { return -1 }
+    return len([1, 2])
+}
+var shadowed = run()
+var after = len([1, 2])";
+        assert!(matches!(run_and_get(src, "first_n"), Value::Int(2)));
+        assert!(matches!(run_and_get(src, "shadowed"), Value::Int(-1)));
+        // лямбда была локальной — снаружи снова builtin
+        assert!(matches!(run_and_get(src, "after"), Value::Int(2)));
+        assert!(matches!(
+            run_and_get("var len = 3\nvar n = len([1])", "n"),
+            Value::Int(1)
+        ));
+    }
+
+    #[test]
+    fn user_range_is_used_in_for_each() {
+        let src = "func range(n: int) {
+    return [n, n]
+}
+var mut total = 0
+for i in range(5) {
+    total = total + i
+}";
+        assert!(matches!(run_and_get(src, "total"), Value::Int(10)));
+    }
+
+    #[test]
+    fn extend_and_remove_at_build_new_lists() {
+        let src = "var xs = [1, 2]
+var joined = extend(xs, [3, 4])
+var removed = remove_at([1, 2, 3], 1)
+var last_gone = remove_at([1, 2, 3], -1)
+var same = xs == [1, 2]";
+        let ints = |xs: &[i64]| xs.iter().map(|&n| Value::Int(n)).collect::<Vec<_>>();
+        assert!(
+            matches!(run_and_get(src, "joined"), Value::List(xs) if *xs == ints(&[1, 2, 3, 4]))
+        );
+        assert!(matches!(run_and_get(src, "removed"), Value::List(xs) if *xs == ints(&[1, 3])));
+        assert!(matches!(run_and_get(src, "last_gone"), Value::List(xs) if *xs == ints(&[1, 2])));
+        assert!(matches!(run_and_get(src, "same"), Value::Bool(true)));
+        assert_eq!(
+            runtime_error("remove_at([1, 2, 3], 3)"),
+            "remove_at(list, i): index 3 out of range for list of length 3"
+        );
+        assert_eq!(
+            runtime_error("extend([1], 2)"),
+            "extend(list, other): both args must be lists, got list [1] and int 2"
+        );
+    }
+
+    #[test]
+    fn dispatch_calls_function_by_key() {
+        let src = "func add(a: int, b: int) {
+    return a + b
+}
+func negate(x: int) {
+    return -x
+}
+var commands = {\"add\": \"add\", \"neg\": \"negate\", \"twice\": fn(x: int) { return x * 2 }}
+var sum = dispatch(commands, \"add\", [2, 3])
+var neg = dispatch(commands, \"neg\", [7])
+var twice = dispatch(commands, \"twice\", [21])";
+        assert!(matches!(run_and_get(src, "sum"), Value::Int(5)));
+        assert!(matches!(run_and_get(src, "neg"), Value::Int(-7)));
+        assert!(matches!(run_and_get(src, "twice"), Value::Int(42)));
+        assert_eq!(
+            runtime_error("dispatch({\"a\": \"f\"}, \"b\", [])"),
+            "dispatch: key str \"b\" not found in table"
+        );
+        assert_eq!(
+            runtime_error("dispatch({\"a\": \"f\"}, \"a\", [])"),
+            "dispatch: unknown function 'f'"
+        );
+        assert_eq!(
+            runtime_error("dispatch({1: 2}, 1, [])"),
+            "dispatch: entry for key int 1 must be a function or a function name, got int 2"
+        );
+    }
+
+    #[test]
+    fn abs_diff_and_saturating_arithmetic_do_not_overflow() {
+        let src = "var max = 9223372036854775807
+var min = -max - 1
+var diff = abs_diff(3, 7)
+var same = abs_diff(7, 3)
+var up = add_sat(max, 10)
+var down = sub_sat(min, 10)
+var plain = add_sat(2, 3) + sub_sat(2, 3)";
+        assert!(matches!(run_and_get(src, "diff"), Value::Int(4)));
+        assert!(matches!(run_and_get(src, "same"), Value::Int(4)));
+        assert!(matches!(run_and_get(src, "up"), Value::Int(i64::MAX)));
+        assert!(matches!(run_and_get(src, "down"), Value::Int(i64::MIN)));
+        assert!(matches!(run_and_get(src, "plain"), Value::Int(4)));
+        assert_eq!(
+            runtime_error("var min = -9223372036854775807 - 1\nvar x = abs_diff(min, 1)"),
+            "integer overflow in 'abs_diff'"
+        );
+    }
+
+    /// Хелпер: текст ошибки вместе с позицией, как её печатает `main`.
+    fn located_error(src: &str) -> String {
+        Interpreter::new()
+            .run(&parse_source(src))
+            .expect_err("expected a runtime error")
+            .to_string()
+    }
+
+    #[test]
+    fn runtime_errors_report_source_line() {
+        let src = "var a = 1
+
+# комментарий
+var b = a + \"x\"";
+        assert_eq!(
+            located_error(src),
+            "Type error in '+': cannot add int 1 and str \"x\" at line 4"
+        );
+        // ошибка внутри функции — строка в её теле, а не место вызова
+        let src = "func f(n: int) {
+    var x = n
+    return missing + x
+}
+print(f(1))";
+        assert_eq!(located_error(src), "Undefined variable missing at line 3");
+        // у операторов — своя позиция, даже если значение — литерал
+        assert_eq!(
+            located_error("var ok = 1\nvar x: int = \"s\""),
+            "type error: variable 'x' declared as int, but value is Str(\"s\") at line 2"
+        );
+        assert_eq!(
+            located_error("var n = 0\nwhile n {\n}"),
+            "while condition must be bool, got int 0 at line 2"
+        );
+        // в catch попадает только текст ошибки
+        let src = "var mut msg = \"\"\ntry {\n    raise(\"boom\")\n} catch e {\n    msg = e\n}";
+        assert!(matches!(run_and_get(src, "msg"), Value::Str(s) if s == "boom"));
+        assert_eq!(
+            located_error("var a = 1\nraise(\"boom\")"),
+            "boom at line 2"
+        );
+    }
+
+    #[test]
+    fn many_pieces_concatenate_into_one_string() {
+        let src = "var mut parts = []
+var mut s = \"\"
+for i in 2000 {
+    parts = push(parts, str(i))
+    s = s + str(i) + ','
+}
+var built = build_string(parts)
+var mixed = build_string([\"ab\", 'c', \"\", 'd'])
+var prefixed = 'x' + \"yz\"";
+        let expected: String = (0..2000).map(|i| i.to_string()).collect();
+        assert!(matches!(run_and_get(src, "built"), Value::Str(s) if s == expected));
+        let expected: String = (0..2000).map(|i| format!("{},", i)).collect();
+        assert!(matches!(run_and_get(src, "s"), Value::Str(s) if s == expected));
+        assert!(matches!(run_and_get(src, "mixed"), Value::Str(s) if s == "abcd"));
+        assert!(matches!(run_and_get(src, "prefixed"), Value::Str(s) if s == "xyz"));
+        assert_eq!(
+            runtime_error("build_string([\"a\", 1])"),
+            "build_string(pieces): pieces must be str or char, got int 1"
+        );
+    }
+
+    #[test]
+    fn truthy_conditions_are_opt_in() {
+        let truthy = |src: &str, name: &str| {
+            let mut interp = Interpreter::new().with_truthy_conditions(true);
+            interp.run(&parse_source(src)).unwrap();
+            interp.get_var(name).unwrap()
+        };
+        let src = "var mut taken = \"\"
+if [1] {
+    taken = \"list\"
+}
+if \"\" {
+    taken = \"empty string\"
+} elif 0 {
+    taken = \"zero\"
+}
+var mut count = 3
+var mut steps = 0
+while count {
+    count = count - 1
+    steps = steps + 1
+}";
+        assert!(matches!(truthy(src, "taken"), Value::Str(s) if s == "list"));
+        assert!(matches!(truthy(src, "count"), Value::Int(0)));
+        assert!(matches!(truthy(src, "steps"), Value::Int(3)));
+
+        // без флага условие цикла по-прежнему обязано быть bool
+        assert_eq!(
+            runtime_error("var mut n = 1\nwhile n {\n    n = 0\n}"),
+            "while condition must be bool, got int 1"
+        );
+        let src = "var a = bool(0)\nvar b = bool(\"x\")\nvar c = bool([])\nvar d = bool({1: 2})";
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "c"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "d"), Value::Bool(true)));
+    }
+
+    #[test]
+    fn debug_returns_its_argument_unchanged() {
+        let src = "func compute() {
+    return 40
+}
+var y: int = debug(compute()) + 2
+var s = debug(\"3\")
+var xs = debug([1, [2]])";
+        assert!(matches!(run_and_get(src, "y"), Value::Int(42)));
+        assert!(matches!(run_and_get(src, "s"), Value::Str(s) if s == "3"));
+        assert!(matches!(
+            run_and_get(src, "xs"),
+            Value::List(xs) if *xs == vec![Value::Int(1), Value::list(vec![Value::Int(2)])]
+        ));
+    }
+
+    #[test]
+    fn argmax_and_argmin_return_first_extreme_index() {
+        let src = "var hi = argmax([3, 9, 2])
+var lo = argmin([5, 1, 1])
+var tie = argmax([7, 7])
+var word = argmin([\"pear\", \"apple\", \"fig\"])";
+        assert!(matches!(run_and_get(src, "hi"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "lo"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "tie"), Value::Int(0)));
+        assert!(matches!(run_and_get(src, "word"), Value::Int(1)));
+        assert_eq!(runtime_error("argmax([])"), "argmax(list): list is empty");
+        assert_eq!(
+            runtime_error("argmin([1, \"a\"])"),
+            "argmin(list): cannot compare str \"a\" and int 1"
+        );
+    }
+
+    #[test]
+    fn divmod_returns_quotient_and_remainder() {
+        let src = "var qr = divmod(17, 5)
+var q, r = divmod(-7, 2)";
+        assert!(matches!(
+            run_and_get(src, "qr"),
+            Value::Tuple(items) if *items == vec![Value::Int(3), Value::Int(2)]
+        ));
+        assert!(matches!(run_and_get(src, "q"), Value::Int(-3)));
+        assert!(matches!(run_and_get(src, "r"), Value::Int(-1)));
+        assert_eq!(runtime_error("divmod(1, 0)"), "division by zero");
+    }
+
+    #[test]
+    fn take_and_drop_split_a_list_by_count() {
+        let src = "var xs = [1, 2, 3, 4]
+var first = take(xs, 2)
+var rest = drop(xs, 2)
+var all = take(xs, 10)
+var none = drop(xs, 10)";
+        let ints = |xs: &[i64]| xs.iter().map(|&n| Value::Int(n)).collect::<Vec<_>>();
+        assert!(matches!(run_and_get(src, "first"), Value::List(xs) if *xs == ints(&[1, 2])));
+        assert!(matches!(run_and_get(src, "rest"), Value::List(xs) if *xs == ints(&[3, 4])));
+        assert!(matches!(run_and_get(src, "all"), Value::List(xs) if *xs == ints(&[1, 2, 3, 4])));
+        assert!(matches!(run_and_get(src, "none"), Value::List(xs) if xs.is_empty()));
+        assert_eq!(
+            runtime_error("take([1], -1)"),
+            "take(list, n): n must be >= 0, got -1"
+        );
+    }
+
+    #[test]
+    fn iteration_limit_stops_infinite_loops() {
+        let limited = |src: &str| {
+            let mut interp = Interpreter::new().with_max_iterations(100);
+            let result = interp.run(&parse_source(src));
+            (result, interp)
+        };
+        let (result, interp) = limited("var mut n = 0\nwhile (true) {\n    n = n + 1\n}");
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "iteration limit exceeded (100 loop iterations)"
+        );
+        assert!(matches!(interp.get_var("n"), Some(Value::Int(100))));
+
+        // бюджет общий на все циклы запуска
+        let (result, _) = limited("for i in 60 {\n}\nloop {\n}");
+        assert!(result.is_err());
+        let (result, _) = limited("for i in 60 {\n}\nfor i in 0..40 {\n}\nrepeat 0 {\n}");
+        assert_eq!(result, Ok(()));
+
+        // `try` не перехватывает исчерпание бюджета
+        let (result, interp) = limited(
+            "var mut caught = false\ntry {\n    while (true) {\n    }\n} catch e {\n    caught = true\n}\nvar after = true",
+        );
+        assert_eq!(result, Err(RuntimeError::IterationLimit(100)));
+        assert!(matches!(interp.get_var("caught"), Some(Value::Bool(false))));
+        assert!(interp.get_var("after").is_none());
+
+        // повторный запуск того же интерпретатора начинает с полного бюджета
+        let mut interp = Interpreter::new().with_max_iterations(100);
+        let program = parse_source("for i in 60 {\n}");
+        assert_eq!(interp.run(&program), Ok(()));
+        assert_eq!(interp.run(&program), Ok(()));
+    }
+
+    #[test]
+    fn sort_by_is_stable_and_supports_descending() {
+        let src = "func length(s: str) {
+    return len(s)
+}
+var words = [\"ccc\", \"a\", \"bb\", \"x\", \"yy\"]
+var asc = sort_by(length, words)
+var desc = sort_by(length, words, true)";
+        let strs =
+            |items: &[&str]| Value::list(items.iter().map(|s| Value::Str(s.to_string())).collect());
+        assert_eq!(
+            run_and_get(src, "asc"),
+            strs(&["a", "x", "bb", "yy", "ccc"])
+        );
+        assert_eq!(
+            run_and_get(src, "desc"),
+            strs(&["ccc", "bb", "yy", "a", "x"])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot compare keys")]
+    fn sort_by_rejects_mixed_keys() {
+        run_source("sort_by(fn(x: list) { return x[0] }, [[1], [\"a\"]])");
+    }
+
+    #[test]
+    fn chars_bytes_and_to_list_convert_to_lists() {
+        let src = "var cs = chars(\"abc\")\nvar n = len(cs)\nvar bs = bytes(\"A\")\nvar r = to_list(3)\nvar ks = to_list({\"k\": 1})\nvar ts = to_list((1, \"a\"))";
+        assert!(matches!(run_and_get(src, "n"), Value::Int(3)));
+        assert_eq!(
+            run_and_get(src, "cs"),
+            Value::list(vec![Value::Char('a'), Value::Char('b'), Value::Char('c')])
+        );
+        assert_eq!(run_and_get(src, "bs"), Value::list(vec![Value::Int(65)]));
+        assert_eq!(
+            run_and_get(src, "r"),
+            Value::list(vec![Value::Int(0), Value::Int(1), Value::Int(2)])
+        );
+        assert_eq!(
+            run_and_get(src, "ks"),
+            Value::list(vec![Value::Str("k".to_string())])
+        );
+        assert_eq!(
+            run_and_get(src, "ts"),
+            Value::list(vec![Value::Int(1), Value::Str("a".to_string())])
+        );
+    }
+
+    #[test]
+    fn round_and_format_float() {
+        let src = "var a = round(2.6)\nvar b = round(-2.5)\nvar c = round(1.23456, 3)\nvar s = format_float(3.14159, 2)\nvar t = format_float(2, 1)";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(3)));
+        assert!(matches!(run_and_get(src, "b"), Value::Int(-3)));
+        assert!(matches!(run_and_get(src, "c"), Value::Float(x) if x == 1.235));
+        assert!(matches!(run_and_get(src, "s"), Value::Str(s) if s == "3.14"));
+        assert!(matches!(run_and_get(src, "t"), Value::Str(s) if s == "2.0"));
+    }
+
+    #[test]
+    fn round_with_many_digits_does_not_overflow() {
+        let src = "var a = round(10000000000.0, 300)\nvar b = round(-20000000000.5, 300)";
+        assert!(matches!(run_and_get(src, "a"), Value::Float(x) if x == 10000000000.0));
+        assert!(matches!(run_and_get(src, "b"), Value::Float(x) if x == -20000000000.5));
+    }
+
+    #[test]
+    #[should_panic(expected = "digits must not be negative")]
+    fn round_rejects_negative_digits() {
+        run_source("round(1.5, -1)");
+    }
+
+    #[test]
+    fn last_expression_is_the_implicit_return_value() {
+        let src = "func sq(x: int) -> int {\n    x * x\n}\nfunc early(x: int) {\n    if (x > 0) {\n        return \"pos\"\n    }\n    \"other\"\n}\nvar a = sq(7)\nvar b = early(1)\nvar c = early(-1)\nvar d = fn(x: int) { x + 1 }(1)";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(49)));
+        assert!(matches!(run_and_get(src, "b"), Value::Str(s) if s == "pos"));
+        assert!(matches!(run_and_get(src, "c"), Value::Str(s) if s == "other"));
+        assert!(matches!(run_and_get(src, "d"), Value::Int(2)));
+    }
+
+    #[test]
+    fn trailing_print_or_loop_still_returns_unit() {
+        let src = "func log(x: int) {\n    print(x)\n}\nfunc spin() {\n    for i in 3 {\n        i\n    }\n}\nvar mut a = 0\nvar mut b = 0\nif (log(1) == print()) {\n    a = 1\n}\nif (spin() == print()) {\n    b = 1\n}";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "b"), Value::Int(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "declared to return int")]
+    fn declared_return_type_is_checked() {
+        run_source("func f() -> int {\n    \"nope\"\n}\nf()");
+    }
+
+    #[test]
+    fn tuples_index_compare_and_print() {
+        let src = "var t: tuple = (1, \"a\", true)\nvar a = t[0]\nvar b = t[-2]\nvar same = t == (1, \"a\", true)\nvar diff = (1, 2) != (2, 1)\nvar paren = (5)\nvar n = len(t)";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "b"), Value::Str(s) if s == "a"));
+        assert!(matches!(run_and_get(src, "same"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "diff"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "paren"), Value::Int(5)));
+        assert!(matches!(run_and_get(src, "n"), Value::Int(3)));
+        assert_eq!(
+            run_capture("print((1, \"a\"), (2,), ())"),
+            "(1, \"a\") (2,) ()\n"
+        );
+    }
+
+    #[test]
+    fn pop_returns_last_and_rest() {
+        let src = "var p = pop([1, 2, 3])\nvar last = p[0]\nvar rest = p[1]";
+        assert!(matches!(run_and_get(src, "last"), Value::Int(3)));
+        assert_eq!(
+            run_and_get(src, "rest"),
+            Value::list(vec![Value::Int(1), Value::Int(2)])
+        );
+    }
+
+    #[test]
+    fn destructuring_binds_list_and_tuple_elements() {
+        let src = "var a, b = [1, \"two\"]\nvar mut x, y = (10, 20)\nx, y = (y, x)\nvar last, rest = pop([1, 2, 3])";
+        assert!(matches!(run_and_get(src, "a"), Value::Int(1)));
+        assert!(matches!(run_and_get(src, "b"), Value::Str(s) if s == "two"));
+        assert!(matches!(run_and_get(src, "x"), Value::Int(20)));
+        assert!(matches!(run_and_get(src, "y"), Value::Int(10)));
+        assert!(matches!(run_and_get(src, "last"), Value::Int(3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot destructure 3 values into 2 variables")]
+    fn destructuring_length_mismatch_is_an_error() {
+        run_source("var a, b = [1, 2, 3]");
+    }
+
+    #[test]
+    fn unknown_function_suggests_a_similar_name() {
+        let program = parse_source("func length(s: str) {\n    len(s)\n}\nlenght(\"abc\")");
+        let err = Interpreter::new().run(&program).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown function 'lenght'; did you mean 'length'? at line 4"
+        );
+
+        let err = Interpreter::new()
+            .run(&parse_source("prnt(1)"))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Unknown function 'prnt'; did you mean 'print'? at line 1"
+        );
+
+        let err = Interpreter::new()
+            .run(&parse_source("frobnicate(1)"))
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unknown function 'frobnicate' at line 1");
+    }
+
+    #[test]
+    fn builtin_name_lists_match_the_dispatchers() {
+        let mut sink = Vec::new();
+        for name in stdlib::BUILTIN_NAMES {
+            let result = stdlib::call_builtin(name, &[], &mut sink);
+            assert!(!matches!(result, Ok(None)), "'{}' is not a builtin", name);
+        }
+        let mut interp = Interpreter::new().with_input(io::empty());
+        for name in STATEFUL_BUILTIN_NAMES {
+            let result = interp.call_stateful_builtin(name, &[]);
+            assert!(!matches!(result, Ok(None)), "'{}' is not a builtin", name);
+        }
+    }
+
+    #[test]
+    fn all_and_any_aggregate_bools() {
+        let src = "var a = all([true, true])\nvar b = all([true, false])\nvar c = any([false, true])\nvar d = any([false])\nvar e = all([])\nvar f = any([])\nvar g = all(map([2, 4], fn(x: int) { x > 1 }))";
+        assert!(matches!(run_and_get(src, "a"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "b"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "c"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "d"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "e"), Value::Bool(true)));
+        assert!(matches!(run_and_get(src, "f"), Value::Bool(false)));
+        assert!(matches!(run_and_get(src, "g"), Value::Bool(true)));
+    }
+
+    #[test]
+    #[should_panic(expected = "any(list): elements must be bool")]
+    fn any_rejects_non_bool_elements() {
+        run_source("any([1])");
+    }
+
+    #[test]
+    fn copy_is_independent_of_the_original() {
+        let src =
+            "var xs = [[1, 2], [3]]\nvar mut ys = copy(xs)\nys = push(ys, [4])\nvar n = len(ys)";
+        let xs = run_and_get(src, "xs");
+        assert_eq!(
+            xs,
+            Value::list(vec![
+                Value::list(vec![Value::Int(1), Value::Int(2)]),
+                Value::list(vec![Value::Int(3)]),
+            ])
+        );
+        assert!(matches!(run_and_get(src, "n"), Value::Int(3)));
+
+        // копия равна оригиналу, но ни на какой глубине не делит с ним память
+        let copied = stdlib::call_builtin("copy", std::slice::from_ref(&xs), &mut Vec::new())
+            .unwrap()
+            .unwrap();
+        assert_eq!(copied, xs);
+        match (&xs, &copied) {
+            (Value::List(a), Value::List(b)) => {
+                assert!(!Rc::ptr_eq(a, b));
+                for (x, y) in a.iter().zip(b.iter()) {
+                    match (x, y) {
+                        (Value::List(x), Value::List(y)) => assert!(!Rc::ptr_eq(x, y)),
+                        _ => unreachable!(),
+                    }
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
 }
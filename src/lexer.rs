@@ -10,6 +10,7 @@
 // ===== Токены =====
 
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Token {
     // --- Структурные токены потока ---
     /// Перевод строки `\n`
@@ -23,12 +24,18 @@ pub enum Token {
 
     /// Ключевое слово `var`
     Kwvar,
-    /// Ключевое слово `mut` (пока не используется в парсере, но зарезервировано)
+    /// Ключевое слово `mut` (`var mut x = ...` — изменяемая переменная)
     KwMut,
     /// Ключевое слово `func`
     KwFunc,
+    /// Ключевое слово `fn` (анонимная функция: `fn(x: int) { ... }`)
+    KwFn,
     /// Ключевое слово `return`
     KwReturn,
+    /// Ключевое слово `break`
+    KwBreak,
+    /// Ключевое слово `continue`
+    KwContinue,
     /// Ключевое слово `if`
     KwIf,
     /// Ключевое слово `elif`
@@ -45,12 +52,38 @@ pub enum Token {
     KwFalse,
     /// Ключевое слово `while`
     KwWhile,
+    /// Ключевое слово `do` (`do { ... } while (cond)`)
+    KwDo,
+    /// Ключевое слово `repeat` (`repeat n { ... }`)
+    KwRepeat,
+    /// Ключевое слово `loop` (бесконечный цикл)
+    KwLoop,
+    /// Ключевое слово `match`
+    KwMatch,
+    /// Ключевое слово `try` (`try { ... } catch e { ... }`)
+    KwTry,
+    /// Ключевое слово `catch`
+    KwCatch,
+    /// Ключевое слово `global` (`global x` — присваивать глобальной `x`)
+    KwGlobal,
+    /// Ключевое слово `import`
+    KwImport,
+    /// Ключевое слово `and` (синоним `&&`)
+    KwAnd,
+    /// Ключевое слово `or` (синоним `||`)
+    KwOr,
+    /// Ключевое слово `not` (синоним `!`)
+    KwNot,
 
     // --- Литералы ---
     /// Целочисленный литерал: `123`
     IntLiteral(i64),
+    /// Литерал с плавающей точкой: `3.14`
+    FloatLiteral(f64),
     /// Строковый литерал: `"hello"`
     StrLiteral(String),
+    /// Символьный литерал: `'a'`, `'\n'`
+    CharLiteral(char),
 
     // --- Арифметические операторы ---
     Plus,    // +
@@ -68,6 +101,11 @@ pub enum Token {
     Gt,    // >
     GtEq,  // >=
 
+    // --- Логические операторы ---
+    AndAnd, // &&
+    OrOr,   // ||
+    Bang,   // !
+
     // --- Знаки пунктуации / скобки ---
     LParen,   // (
     RParen,   // )
@@ -78,6 +116,47 @@ pub enum Token {
     Colon,    // :
     Semi,     // ;
     Comma,    // ,
+    Arrow,    // -> (тип результата функции)
+    DotDot,   // .. (диапазон в for: `for i in 0..n`)
+}
+
+impl Token {
+    /// Если токен — ключевое слово, возвращает его текст.
+    /// Нужно парсеру, чтобы понятно ругаться на зарезервированные имена.
+    pub fn keyword_text(&self) -> Option<&'static str> {
+        use Token::*;
+
+        let text = match self {
+            Kwvar => "var",
+            KwMut => "mut",
+            KwFunc => "func",
+            KwFn => "fn",
+            KwReturn => "return",
+            KwBreak => "break",
+            KwContinue => "continue",
+            KwIf => "if",
+            KwElseIf => "elif",
+            KwElse => "else",
+            KwFor => "for",
+            KwIn => "in",
+            KwTrue => "true",
+            KwFalse => "false",
+            KwWhile => "while",
+            KwDo => "do",
+            KwRepeat => "repeat",
+            KwLoop => "loop",
+            KwMatch => "match",
+            KwTry => "try",
+            KwCatch => "catch",
+            KwGlobal => "global",
+            KwImport => "import",
+            KwAnd => "and",
+            KwOr => "or",
+            KwNot => "not",
+            _ => return None,
+        };
+        Some(text)
+    }
 }
 
 // ===== Лексер =====
@@ -86,10 +165,14 @@ pub enum Token {
 /// Хранит:
 ///   - `input` — весь текст программы
 ///   - `pos`   — текущий индекс (указатель) в этом массиве
+///   - `line`  — номер строки (с 1), на которой стоит `pos`
 #[derive(Clone)]
 pub struct Lexer {
     input: Vec<char>,
     pos: usize, // текущий индекс в input
+    line: usize,
+    /// Строка, с которой начался последний выданный токен.
+    token_line: usize,
 }
 
 impl Lexer {
@@ -98,14 +181,26 @@ impl Lexer {
         Self {
             input: src.chars().collect(),
             pos: 0,
+            line: 1,
+            token_line: 1,
         }
     }
 
+    /// Номер строки (с 1), с которой начался последний токен из `next_token`.
+    pub fn token_line(&self) -> usize {
+        self.token_line
+    }
+
     /// Подсмотреть текущий символ (без сдвига позиции).
     fn peek(&self) -> Option<char> {
         self.input.get(self.pos).copied()
     }
 
+    /// Подсмотреть символ через один от текущего.
+    fn peek_next(&self) -> Option<char> {
+        self.input.get(self.pos + 1).copied()
+    }
+
     /// Считать текущий символ и сдвинуть позицию вперёд на 1.
     fn advance(&mut self) -> Option<char> {
         if self.pos >= self.input.len() {
@@ -113,14 +208,17 @@ impl Lexer {
         } else {
             let ch = self.input[self.pos];
             self.pos += 1;
+            if ch == '\n' {
+                self.line += 1;
+            }
             Some(ch)
         }
     }
 
-    /// Пропустить пробелы и табы (но не перенос строки).
+    /// Пропустить пробелы, табы и `\r` (но не перенос строки).
     fn skip_spaces(&mut self) {
         while let Some(ch) = self.peek() {
-            if ch == ' ' || ch == '\t' {
+            if ch == ' ' || ch == '\t' || ch == '\r' {
                 self.advance();
             } else {
                 break;
@@ -128,16 +226,34 @@ impl Lexer {
         }
     }
 
+    /// Пропустить комментарий до конца строки (сам `\n` остаётся —
+    /// он нужен парсеру как разделитель операторов).
+    fn skip_line_comment(&mut self) {
+        while let Some(ch) = self.peek() {
+            if ch == '\n' {
+                break;
+            }
+            self.advance();
+        }
+    }
+
     /// Считать следующий токен из входа.
     ///
     /// Основной метод лексера: всё остальное — помощники.
     pub fn next_token(&mut self) -> Token {
         use Token::*;
 
-        // сначала убираем пробелы / табы
+        // сначала убираем пробелы / табы и комментарии `# ...` / `// ...`
         self.skip_spaces();
+        while self.peek() == Some('#')
+            || (self.peek() == Some('/') && self.peek_next() == Some('/'))
+        {
+            self.skip_line_comment();
+            self.skip_spaces();
+        }
 
         // берём следующий символ
+        self.token_line = self.line;
         let ch = match self.advance() {
             Some(c) => c,
             None => return EOF,
@@ -159,9 +275,20 @@ impl Lexer {
             // начало строкового литерала
             '"' => self.lex_string(),
 
+            // начало символьного литерала
+            '\'' => self.lex_char(),
+
             // односивольные операторы
             '+' => Plus,
-            '-' => Minus,
+            // '->' или '-'
+            '-' => {
+                if self.peek() == Some('>') {
+                    self.advance();
+                    Arrow
+                } else {
+                    Minus
+                }
+            }
             '*' => Star,
             '/' => Slash,
             '%' => Percent,
@@ -177,6 +304,16 @@ impl Lexer {
             ';' => Semi,
             ',' => Comma,
 
+            // '..' (одиночная точка бывает только внутри float-литерала)
+            '.' => {
+                if self.peek() == Some('.') {
+                    self.advance();
+                    DotDot
+                } else {
+                    panic!("Unexpected '.' without '.'");
+                }
+            }
+
             // '=' или '=='
             '=' => {
                 if self.peek() == Some('=') {
@@ -187,15 +324,33 @@ impl Lexer {
                 }
             }
 
-            // '!='
+            // '!=' или '!'
             '!' => {
                 if self.peek() == Some('=') {
                     self.advance();
                     NotEq
                 } else {
-                    // на данном этапе просто паникуем,
-                    // позже можно превратить в нормальную лексическую ошибку
-                    panic!("Unexpected '!' without '='");
+                    Bang
+                }
+            }
+
+            // '&&'
+            '&' => {
+                if self.peek() == Some('&') {
+                    self.advance();
+                    AndAnd
+                } else {
+                    panic!("Unexpected '&' without '&'");
+                }
+            }
+
+            // '||'
+            '|' => {
+                if self.peek() == Some('|') {
+                    self.advance();
+                    OrOr
+                } else {
+                    panic!("Unexpected '|' without '|'");
                 }
             }
 
@@ -219,9 +374,7 @@ impl Lexer {
                 }
             }
 
-            // TODO: здесь можно добавить поддержку комментариев:
-            //   - однострочные //...
-            //   - многострочные /* ... */
+            // TODO: здесь можно добавить многострочные комментарии /* ... */,
             // а также сделать аккуратную систему ошибок вместо panic!
             other => panic!("Unexpected character: {:?}", other),
         }
@@ -231,20 +384,80 @@ impl Lexer {
     ///
     /// На входе уже считана первая цифра `first_digit`.
     fn lex_number(&mut self, first_digit: char) -> Token {
+        // 0x.. / 0b.. / 0o.. — целое в другой системе счисления
+        if first_digit == '0'
+            && let Some((radix, name)) = match self.peek() {
+                Some('x') => Some((16, "hex")),
+                Some('b') => Some((2, "binary")),
+                Some('o') => Some((8, "octal")),
+                _ => None,
+            }
+        {
+            self.advance(); // съели 'x' / 'b' / 'o'
+            return self.lex_radix_number(radix, name);
+        }
+
         let mut s = String::new();
         s.push(first_digit);
 
+        // `_` — разделитель разрядов: 1_000_000
         while let Some(ch) = self.peek() {
             if ch.is_ascii_digit() {
                 s.push(ch);
                 self.advance();
+            } else if ch == '_' {
+                self.advance();
+            } else {
+                break;
+            }
+        }
+
+        // дробная часть: точка, за которой сразу идёт цифра (`1.5`, но не `1.`)
+        if self.peek() == Some('.') && self.peek_next().is_some_and(|ch| ch.is_ascii_digit()) {
+            s.push('.');
+            self.advance();
+            while let Some(ch) = self.peek() {
+                if ch.is_ascii_digit() {
+                    s.push(ch);
+                    self.advance();
+                } else {
+                    break;
+                }
+            }
+            return Token::FloatLiteral(s.parse::<f64>().unwrap());
+        }
+
+        match s.parse::<i64>() {
+            Ok(value) => Token::IntLiteral(value),
+            Err(_) => panic!("Integer literal {} is out of range for int", s),
+        }
+    }
+
+    /// Цифры целого литерала после префикса `0x` / `0b` / `0o`.
+    /// Буквы и цифры, которые не подходят для основания, — ошибка.
+    fn lex_radix_number(&mut self, radix: u32, name: &str) -> Token {
+        let mut digits = String::new();
+        while let Some(ch) = self.peek() {
+            if ch.is_ascii_alphanumeric() {
+                if !ch.is_digit(radix) {
+                    panic!("Invalid digit '{}' in {} literal", ch, name);
+                }
+                digits.push(ch);
+                self.advance();
+            } else if ch == '_' {
+                self.advance();
             } else {
                 break;
             }
         }
 
-        let value = s.parse::<i64>().unwrap();
-        Token::IntLiteral(value)
+        if digits.is_empty() {
+            panic!("Expected digits after the {} literal prefix", name);
+        }
+        match i64::from_str_radix(&digits, radix) {
+            Ok(value) => Token::IntLiteral(value),
+            Err(_) => panic!("{} literal is out of range for int", name),
+        }
     }
 
     /// Разбор идентификатора или ключевого слова.
@@ -267,15 +480,29 @@ impl Lexer {
             "var" => Token::Kwvar,
             "mut" => Token::KwMut,
             "func" => Token::KwFunc,
+            "fn" => Token::KwFn,
             "return" => Token::KwReturn,
+            "break" => Token::KwBreak,
+            "continue" => Token::KwContinue,
             "if" => Token::KwIf,
             "elif" => Token::KwElseIf,
             "else" => Token::KwElse,
             "while" => Token::KwWhile,
+            "do" => Token::KwDo,
+            "repeat" => Token::KwRepeat,
+            "loop" => Token::KwLoop,
             "for" => Token::KwFor,
             "in" => Token::KwIn,
             "true" => Token::KwTrue,
             "false" => Token::KwFalse,
+            "match" => Token::KwMatch,
+            "try" => Token::KwTry,
+            "catch" => Token::KwCatch,
+            "global" => Token::KwGlobal,
+            "import" => Token::KwImport,
+            "and" => Token::KwAnd,
+            "or" => Token::KwOr,
+            "not" => Token::KwNot,
             _ => Token::Ident(s),
         }
     }
@@ -284,21 +511,258 @@ impl Lexer {
     ///
     /// Ожидается, что ведущая кавычка уже была съедена.
     fn lex_string(&mut self) -> Token {
+        // `"""` — многострочная строка
+        if self.peek() == Some('"') && self.peek_next() == Some('"') {
+            self.advance();
+            self.advance();
+            return self.lex_triple_string();
+        }
+
         let mut s = String::new();
 
         while let Some(ch) = self.advance() {
             match ch {
                 '"' => break, // закрывающая кавычка
                 '\n' => panic!("String literal not closed before newline"),
+                '\\' => s.push(self.lex_escape()),
                 _ => s.push(ch),
             }
         }
 
         Token::StrLiteral(s)
     }
+
+    /// Разбор символьного литерала `'a'` — ровно один символ или escape.
+    ///
+    /// Ожидается, что открывающая `'` уже была съедена.
+    fn lex_char(&mut self) -> Token {
+        let ch = match self.advance() {
+            Some('\\') => self.lex_escape(),
+            Some('\'') => panic!("Empty char literal ''"),
+            Some('\n') | None => panic!("Char literal not closed"),
+            Some(ch) => ch,
+        };
+        if self.advance() != Some('\'') {
+            panic!("Char literal must contain exactly one character");
+        }
+        Token::CharLiteral(ch)
+    }
+
+    /// Escape-последовательность внутри строки или символа: `\n`, `\t`, `\r`, `\"`, `\'`, `\\`.
+    ///
+    /// Ожидается, что обратный слэш уже был съеден.
+    fn lex_escape(&mut self) -> char {
+        match self.advance() {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('"') => '"',
+            Some('\'') => '\'',
+            Some('\\') => '\\',
+            Some(other) => panic!("Unknown escape sequence '\\{}' in string literal", other),
+            None => panic!("String literal not closed before end of input"),
+        }
+    }
+
+    /// Разбор многострочного литерала `"""..."""`.
+    /// Переводы строк внутри сохраняются как есть.
+    ///
+    /// Ожидается, что открывающие `"""` уже были съедены.
+    fn lex_triple_string(&mut self) -> Token {
+        let mut s = String::new();
+
+        loop {
+            match self.advance() {
+                Some('"') if self.peek() == Some('"') && self.peek_next() == Some('"') => {
+                    self.advance();
+                    self.advance();
+                    return Token::StrLiteral(s);
+                }
+                Some('\\') => s.push(self.lex_escape()),
+                Some(ch) => s.push(ch),
+                None => panic!("Triple-quoted string literal not closed before end of input"),
+            }
+        }
+    }
+}
+
+/// Прогнать лексер по всему тексту и собрать токены (включая финальный `EOF`).
+pub fn tokenize(src: &str) -> Vec<Token> {
+    let mut lexer = Lexer::new(src);
+    let mut tokens = Vec::new();
+    loop {
+        let token = lexer.next_token();
+        let done = token == Token::EOF;
+        tokens.push(token);
+        if done {
+            return tokens;
+        }
+    }
 }
 
 // TODO:
-//  - поддержка комментариев
+//  - многострочные комментарии /* ... */
 //  - нормальная система лексических ошибок (с позициями), вместо простых panic!
-//  - возможно, поддержка разных видов переноса строк (\r\n и т.п.)
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenize_short_program() {
+        use Token::*;
+
+        let tokens = tokenize("var mut x: int = 10\nprint(x >= 2)");
+        assert_eq!(
+            tokens,
+            vec![
+                Kwvar,
+                KwMut,
+                Ident("x".to_string()),
+                Colon,
+                Ident("int".to_string()),
+                Eq,
+                IntLiteral(10),
+                Newline,
+                Ident("print".to_string()),
+                LParen,
+                Ident("x".to_string()),
+                GtEq,
+                IntLiteral(2),
+                RParen,
+                EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn token_line_counts_newlines_in_comments_and_strings() {
+        let mut lexer = Lexer::new("a # comment\n\"\"\"x\ny\"\"\" b\n\n  c");
+        let mut lines = Vec::new();
+        loop {
+            let token = lexer.next_token();
+            if token == Token::EOF {
+                break;
+            }
+            lines.push((token, lexer.token_line()));
+        }
+        assert_eq!(
+            lines,
+            vec![
+                (Token::Ident("a".to_string()), 1),
+                (Token::Newline, 1),
+                (Token::StrLiteral("x\ny".to_string()), 2),
+                (Token::Ident("b".to_string()), 3),
+                (Token::Newline, 3),
+                (Token::Newline, 4),
+                (Token::Ident("c".to_string()), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn triple_quoted_string_spans_lines() {
+        let tokens = tokenize("var s = \"\"\"first\nsecond \"quoted\" line\"\"\"\nprint(s)");
+        assert_eq!(
+            tokens[3],
+            Token::StrLiteral("first\nsecond \"quoted\" line".to_string())
+        );
+        assert_eq!(tokens[4], Token::Newline);
+        assert_eq!(
+            tokenize("\"\"\"\"\"\"")[0],
+            Token::StrLiteral(String::new())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "not closed before end of input")]
+    fn unterminated_triple_quoted_string() {
+        tokenize("\"\"\"never closed\n");
+    }
+
+    #[test]
+    fn string_escapes() {
+        let lexed = |src: &str| match &tokenize(src)[0] {
+            Token::StrLiteral(s) => s.clone(),
+            other => panic!("expected string literal, got {:?}", other),
+        };
+        assert_eq!(lexed(r#""a\nb""#), "a\nb");
+        assert_eq!(lexed(r#""\"q\"""#), "\"q\"");
+        assert_eq!(lexed(r#""tab\there\\ \r""#), "tab\there\\ \r");
+        assert_eq!(lexed(r#""""line\n""""#), "line\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown escape sequence '\\q'")]
+    fn unknown_escape_is_an_error() {
+        tokenize(r#""\q""#);
+    }
+
+    #[test]
+    fn integer_literals_in_other_bases() {
+        assert_eq!(tokenize("0xFF")[0], Token::IntLiteral(255));
+        assert_eq!(tokenize("0b1010")[0], Token::IntLiteral(10));
+        assert_eq!(tokenize("0o17")[0], Token::IntLiteral(15));
+        assert_eq!(tokenize("0xff_ff")[0], Token::IntLiteral(0xffff));
+        assert_eq!(tokenize("1_000")[0], Token::IntLiteral(1000));
+        assert_eq!(tokenize("0")[0], Token::IntLiteral(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Integer literal 9223372036854775808 is out of range for int")]
+    fn too_large_integer_literal() {
+        tokenize("9223372036854775808");
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid digit '2' in binary literal")]
+    fn invalid_digit_for_base() {
+        tokenize("0b102");
+    }
+
+    #[test]
+    fn range_dots_are_not_part_of_a_number() {
+        use Token::*;
+
+        assert_eq!(
+            tokenize("0..5 1.5"),
+            vec![IntLiteral(0), DotDot, IntLiteral(5), FloatLiteral(1.5), EOF]
+        );
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        use Token::*;
+
+        let tokens = tokenize("# header\nvar x = 1 // tail\n  # indented\r\n");
+        assert_eq!(
+            tokens,
+            vec![
+                Newline,
+                Kwvar,
+                Ident("x".to_string()),
+                Eq,
+                IntLiteral(1),
+                Newline,
+                Newline,
+                EOF,
+            ]
+        );
+    }
+
+    #[test]
+    fn char_literals() {
+        use Token::*;
+
+        assert_eq!(
+            tokenize("'a' '\\n' '\\''"),
+            vec![CharLiteral('a'), CharLiteral('\n'), CharLiteral('\''), EOF]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "exactly one character")]
+    fn char_literal_with_two_characters() {
+        tokenize("'ab'");
+    }
+}
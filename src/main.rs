@@ -1,30 +1,58 @@
 use std::env;
 use std::fs;
+use std::process;
 
 mod ast;
+mod checker;
 mod interpreter;
 mod lexer;
+mod optimizer;
 mod parser;
 mod stdlib;
 
-use interpreter::Interpreter;
+use interpreter::{Interpreter, RuntimeError};
 use lexer::Lexer;
 use parser::Parser;
 
+/// Флаги самого интерпретатора — программе в `argv()` они не передаются.
+const INTERPRETER_FLAGS: &[&str] = &["--ast", "--tokens", "-O", "--check", "--truthy"];
+
 fn main() {
     // Получаем аргументы командной строки
     let args: Vec<String> = env::args().collect();
 
+    // Флаги можно указывать в любом месте относительно пути к программе
+    let dump_ast = args.iter().any(|arg| arg == "--ast");
+    let dump_tokens = args.iter().any(|arg| arg == "--tokens");
+    let optimize = args.iter().any(|arg| arg == "-O");
+    let check_only = args.iter().any(|arg| arg == "--check");
+    let truthy = args.iter().any(|arg| arg == "--truthy");
+
     // Ищем файл с расширением .rht
-    let path = args
+    let path_index = args
+        .iter()
+        .position(|arg| arg.ends_with(".rht"))
+        .expect("❌ You must pass a .rht program file as an argument.");
+    let path = args[path_index].clone();
+
+    // Всё после пути (кроме флагов интерпретатора) — аргументы самой программы
+    let program_args: Vec<String> = args[path_index + 1..]
         .iter()
-        .find(|arg| arg.ends_with(".rht"))
-        .expect("❌ You must pass a .rht program file as an argument.")
-        .clone();
+        .filter(|arg| !INTERPRETER_FLAGS.contains(&arg.as_str()))
+        .cloned()
+        .collect();
 
     // Читаем текст программы
     let program_text = fs::read_to_string(&path).expect("❌ Failed to read the program file.");
 
+    // --tokens: только прогнать лексер, по токену на строку
+    if dump_tokens {
+        for token in lexer::tokenize(&program_text) {
+            println!("{:?}", token);
+        }
+        return;
+    }
+
     // Создаём лексер на основе текста
     let lexer = Lexer::new(&program_text);
 
@@ -32,13 +60,40 @@ fn main() {
     let mut parser = Parser::new(lexer);
 
     // Парсим AST
-    let program = parser.parse_program();
+    let mut program = parser.parse_program();
+
+    // --check: статическая проверка без исполнения; код выхода 0 — ошибок нет
+    if check_only {
+        let diagnostics = checker::check(&program);
+        for diagnostic in &diagnostics {
+            eprintln!("❌ Check error: {}", diagnostic);
+        }
+        process::exit(if diagnostics.is_empty() { 0 } else { 1 });
+    }
+
+    // -O: свернуть константы перед исполнением (и перед --ast, чтобы было видно результат)
+    if optimize {
+        program = optimizer::optimize(program);
+    }
 
-    // println!("AST:\n{:#?}", program);
+    // --ast: только показать дерево, ничего не исполняя
+    if dump_ast {
+        println!("{:#?}", program);
+        return;
+    }
 
     // Создаём интерпретатор
-    let mut interp = Interpreter::new();
+    let mut interp = Interpreter::new()
+        .with_args(program_args)
+        .with_truthy_conditions(truthy);
 
     // Исполняем программу
-    interp.run(&program);
+    match interp.run(&program) {
+        Ok(()) => {}
+        Err(RuntimeError::Exit(code)) => process::exit(code),
+        Err(err) => {
+            eprintln!("❌ Runtime error: {}", err);
+            process::exit(1);
+        }
+    }
 }
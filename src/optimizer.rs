@@ -0,0 +1,442 @@
+// optimizer.rs
+//
+// Необязательный проход по AST перед исполнением (флаг `-O`):
+//  - сворачивает операции над литералами: `2 * 3 + 4` -> `10`
+//  - упрощает ветвления и циклы с константным условием
+//
+// Свёртка не должна менять наблюдаемое поведение программы, поэтому
+// операции вычисляются тем же кодом, что и в интерпретаторе
+// (`Interpreter::eval_bin` / `eval_unary`). Если вычисление падает
+// с ошибкой (`1 / 0`, переполнение, ошибка типов), выражение остаётся
+// как есть — ошибка случится при исполнении, как и без оптимизации.
+
+use crate::ast::{BinOp, Expr, Function, Program, Span, Stmt};
+use crate::interpreter::{Interpreter, Value};
+use std::rc::Rc;
+
+/// Оптимизировать программу целиком.
+pub fn optimize(program: Program) -> Program {
+    Program {
+        imports: program.imports,
+        functions: program
+            .functions
+            .into_iter()
+            .map(optimize_function)
+            .collect(),
+        stmts: optimize_block(program.stmts),
+    }
+}
+
+fn optimize_function(func: Function) -> Function {
+    Function {
+        body: optimize_block(func.body),
+        ..func
+    }
+}
+
+/* ========================= ОПЕРАТОРЫ ========================= */
+
+fn optimize_block(stmts: Vec<Stmt>) -> Vec<Stmt> {
+    stmts.into_iter().filter_map(optimize_stmt).collect()
+}
+
+/// `None` — оператор можно выбросить целиком (например, `while (false)`).
+fn optimize_stmt(stmt: Stmt) -> Option<Stmt> {
+    let stmt = match stmt {
+        Stmt::VarDecl {
+            name,
+            mutable,
+            ty,
+            init,
+            span,
+        } => Stmt::VarDecl {
+            name,
+            mutable,
+            ty,
+            init: fold_expr(init),
+            span,
+        },
+        Stmt::VarDestructure {
+            names,
+            mutable,
+            init,
+            span,
+        } => Stmt::VarDestructure {
+            names,
+            mutable,
+            init: fold_expr(init),
+            span,
+        },
+        Stmt::ExprStmt(expr) => Stmt::ExprStmt(fold_expr(expr)),
+        Stmt::Assign { name, expr, span } => Stmt::Assign {
+            name,
+            expr: fold_expr(expr),
+            span,
+        },
+        Stmt::AssignDestructure { names, expr, span } => Stmt::AssignDestructure {
+            names,
+            expr: fold_expr(expr),
+            span,
+        },
+        Stmt::Branch {
+            cond,
+            then_branch,
+            else_if_branches,
+            else_branch,
+        } => return optimize_branch(cond, then_branch, else_if_branches, else_branch),
+        Stmt::ElseIfBranch { cond, then_branch } => Stmt::ElseIfBranch {
+            cond: fold_expr(cond),
+            then_branch: optimize_block(then_branch),
+        },
+        Stmt::While {
+            cond,
+            body,
+            else_branch,
+        } => {
+            let cond = fold_expr(cond);
+            let else_branch = optimize_block(else_branch);
+            if matches!(cond, Expr::Bool(false)) {
+                // Тело не выполнится ни разу, а `else` — выполнится сразу.
+                return optimize_branch(Expr::Bool(true), else_branch, Vec::new(), Vec::new());
+            }
+            Stmt::While {
+                cond,
+                body: optimize_block(body),
+                else_branch,
+            }
+        }
+        Stmt::DoWhile { body, cond } => Stmt::DoWhile {
+            body: optimize_block(body),
+            cond: fold_expr(cond),
+        },
+        Stmt::Repeat { count, body } => Stmt::Repeat {
+            count: fold_expr(count),
+            body: optimize_block(body),
+        },
+        Stmt::Loop { body } => Stmt::Loop {
+            body: optimize_block(body),
+        },
+        Stmt::For {
+            init,
+            cond,
+            step,
+            body,
+            else_branch,
+        } => Stmt::For {
+            init: init.and_then(|s| optimize_stmt(*s)).map(Box::new),
+            cond: cond.map(fold_expr),
+            step: step.and_then(|s| optimize_stmt(*s)).map(Box::new),
+            body: optimize_block(body),
+            else_branch: optimize_block(else_branch),
+        },
+        Stmt::ForEach {
+            var_name,
+            value_name,
+            iter_expr,
+            body,
+            else_branch,
+        } => Stmt::ForEach {
+            var_name,
+            value_name,
+            iter_expr: fold_expr(iter_expr),
+            body: optimize_block(body),
+            else_branch: optimize_block(else_branch),
+        },
+        Stmt::Match {
+            scrutinee,
+            arms,
+            default,
+        } => Stmt::Match {
+            scrutinee: fold_expr(scrutinee),
+            arms: arms
+                .into_iter()
+                .map(|(pattern, body)| (fold_expr(pattern), optimize_block(body)))
+                .collect(),
+            default: optimize_block(default),
+        },
+        Stmt::Return(expr) => Stmt::Return(expr.map(fold_expr)),
+        Stmt::ForRange {
+            var_name,
+            start,
+            end,
+            step,
+            body,
+            else_branch,
+        } => Stmt::ForRange {
+            var_name,
+            start: fold_expr(start),
+            end: fold_expr(end),
+            step: step.map(fold_expr),
+            body: optimize_block(body),
+            else_branch: optimize_block(else_branch),
+        },
+        Stmt::Try {
+            body,
+            err_name,
+            handler,
+        } => Stmt::Try {
+            body: optimize_block(body),
+            err_name,
+            handler: optimize_block(handler),
+        },
+        Stmt::FuncDecl(func) => Stmt::FuncDecl(optimize_function(func)),
+        Stmt::Break | Stmt::Continue | Stmt::Global(_) => stmt,
+    };
+    Some(stmt)
+}
+
+/// if / elif / else с константными условиями:
+///  - ветки с `false` выбрасываются;
+///  - ветка с `true` становится последней — всё, что после неё, недостижимо.
+///
+/// Блоки не "разворачиваются" в окружающий код: у каждой ветки свой scope.
+fn optimize_branch(
+    cond: Expr,
+    then_branch: Vec<Stmt>,
+    else_if_branches: Vec<Stmt>,
+    else_branch: Vec<Stmt>,
+) -> Option<Stmt> {
+    // Собираем все ветки в один список (cond, body) и чистим его.
+    let mut arms = vec![(fold_expr(cond), optimize_block(then_branch))];
+    for branch in else_if_branches {
+        if let Stmt::ElseIfBranch { cond, then_branch } = branch {
+            arms.push((fold_expr(cond), optimize_block(then_branch)));
+        }
+    }
+    let mut else_branch = optimize_block(else_branch);
+
+    let mut kept: Vec<(Expr, Vec<Stmt>)> = Vec::new();
+    for (cond, body) in arms {
+        match cond {
+            Expr::Bool(false) => continue,
+            Expr::Bool(true) => {
+                // Эта ветка выполнится всегда: она становится `else`.
+                else_branch = body;
+                break;
+            }
+            cond => kept.push((cond, body)),
+        }
+    }
+
+    let mut kept = kept.into_iter();
+    let Some((cond, then_branch)) = kept.next() else {
+        // Ни одного неконстантного условия не осталось.
+        if else_branch.is_empty() {
+            return None;
+        }
+        return Some(Stmt::Branch {
+            cond: Expr::Bool(true),
+            then_branch: else_branch,
+            else_if_branches: Vec::new(),
+            else_branch: Vec::new(),
+        });
+    };
+
+    Some(Stmt::Branch {
+        cond,
+        then_branch,
+        else_if_branches: kept
+            .map(|(cond, then_branch)| Stmt::ElseIfBranch { cond, then_branch })
+            .collect(),
+        else_branch,
+    })
+}
+
+/* ========================= ВЫРАЖЕНИЯ ========================= */
+
+/// Свернуть выражение, насколько это возможно.
+fn fold_expr(expr: Expr) -> Expr {
+    match expr {
+        Expr::Binary {
+            left,
+            op,
+            right,
+            span,
+        } => {
+            let left = fold_expr(*left);
+            let right = fold_expr(*right);
+            fold_binary(left, op, right, span)
+        }
+        Expr::CompareChain { first, rest, span } => {
+            let first = fold_expr(*first);
+            let rest: Vec<(BinOp, Expr)> =
+                rest.into_iter().map(|(op, e)| (op, fold_expr(e))).collect();
+            fold_chain(first, rest, span)
+        }
+        Expr::Unary { op, expr, span } => {
+            let expr = fold_expr(*expr);
+            let folded = literal_value(&expr)
+                // ошибку (например, `-` от минимального int) оставляем до исполнения
+                .and_then(|v| Interpreter::eval_unary(&op, v).ok())
+                .and_then(value_to_literal);
+            folded.unwrap_or(Expr::Unary {
+                op,
+                expr: Box::new(expr),
+                span,
+            })
+        }
+        Expr::Call { callee, args, span } => Expr::Call {
+            callee: Box::new(fold_expr(*callee)),
+            args: args.into_iter().map(fold_expr).collect(),
+            span,
+        },
+        Expr::Index {
+            target,
+            index,
+            span,
+        } => Expr::Index {
+            target: Box::new(fold_expr(*target)),
+            index: Box::new(fold_expr(*index)),
+            span,
+        },
+        Expr::ListLiteral(items) => Expr::ListLiteral(items.into_iter().map(fold_expr).collect()),
+        Expr::TupleLiteral(items) => Expr::TupleLiteral(items.into_iter().map(fold_expr).collect()),
+        Expr::DictLiteral(pairs) => Expr::DictLiteral(
+            pairs
+                .into_iter()
+                .map(|(key, value)| (fold_expr(key), fold_expr(value)))
+                .collect(),
+        ),
+        Expr::Lambda(func) => Expr::Lambda(Rc::new(optimize_function((*func).clone()))),
+        Expr::Int(_)
+        | Expr::Float(_)
+        | Expr::Bool(_)
+        | Expr::Str(_)
+        | Expr::Char(_)
+        | Expr::Var { .. } => expr,
+    }
+}
+
+fn fold_binary(left: Expr, op: BinOp, right: Expr, span: Option<Span>) -> Expr {
+    let unfolded = |left, right| Expr::Binary {
+        left: Box::new(left),
+        op,
+        right: Box::new(right),
+        span,
+    };
+
+    // Логические операторы ленивые: правая часть не вычисляется,
+    // если левая уже определила результат.
+    if let BinOp::And | BinOp::Or = op {
+        return match (op, &left, &right) {
+            (BinOp::And, Expr::Bool(false), _) => Expr::Bool(false),
+            (BinOp::Or, Expr::Bool(true), _) => Expr::Bool(true),
+            (_, Expr::Bool(_), Expr::Bool(r)) => Expr::Bool(*r),
+            _ => unfolded(left, right),
+        };
+    }
+
+    match (literal_value(&left), literal_value(&right)) {
+        (Some(l), Some(r)) => match Interpreter::eval_bin(l, &op, r).map(value_to_literal) {
+            Ok(Some(folded)) => folded,
+            _ => unfolded(left, right),
+        },
+        _ => unfolded(left, right),
+    }
+}
+
+/// Цепочка сравнений сворачивается, только если все операнды — литералы.
+fn fold_chain(first: Expr, rest: Vec<(BinOp, Expr)>, span: Option<Span>) -> Expr {
+    let folded = (|| {
+        let mut left = literal_value(&first)?;
+        for (op, operand) in &rest {
+            let right = literal_value(operand)?;
+            match Interpreter::eval_bin(left, op, right.clone()).ok()? {
+                Value::Bool(true) => left = right,
+                Value::Bool(false) => return Some(false),
+                _ => return None,
+            }
+        }
+        Some(true)
+    })();
+
+    match folded {
+        Some(b) => Expr::Bool(b),
+        None => Expr::CompareChain {
+            first: Box::new(first),
+            rest,
+            span,
+        },
+    }
+}
+
+/// Значение литерала (`None`, если выражение — не литерал).
+fn literal_value(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::Int(n) => Some(Value::Int(*n)),
+        Expr::Float(x) => Some(Value::Float(*x)),
+        Expr::Bool(b) => Some(Value::Bool(*b)),
+        Expr::Str(s) => Some(Value::Str(s.clone())),
+        Expr::Char(c) => Some(Value::Char(*c)),
+        _ => None,
+    }
+}
+
+/// Обратно из значения в литерал (списки и Unit не сворачиваем).
+fn value_to_literal(value: Value) -> Option<Expr> {
+    match value {
+        Value::Int(n) => Some(Expr::Int(n)),
+        Value::Float(x) => Some(Expr::Float(x)),
+        Value::Bool(b) => Some(Expr::Bool(b)),
+        Value::Str(s) => Some(Expr::Str(s)),
+        Value::Char(c) => Some(Expr::Char(c)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::Lexer;
+    use crate::parser::Parser;
+
+    fn optimize_source(src: &str) -> Program {
+        let mut parser = Parser::new(Lexer::new(src));
+        optimize(parser.parse_program())
+    }
+
+    /// Инициализатор первого `var` в программе.
+    fn first_init(program: &Program) -> &Expr {
+        match &program.stmts[0] {
+            Stmt::VarDecl { init, .. } => init,
+            other => panic!("expected var declaration, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn folds_constant_arithmetic() {
+        let program = optimize_source("var x = 2 + 3");
+        assert!(matches!(first_init(&program), Expr::Int(5)));
+
+        let program = optimize_source("var x = 2 * 3 + 4 > 9 and not false");
+        assert!(matches!(first_init(&program), Expr::Bool(true)));
+    }
+
+    #[test]
+    fn leaves_failing_operations_unfolded() {
+        let program = optimize_source("var x = 1 / 0");
+        assert!(matches!(
+            first_init(&program),
+            Expr::Binary { op: BinOp::Div, .. }
+        ));
+
+        let program = optimize_source("var x = 9223372036854775807 + 1");
+        assert!(matches!(first_init(&program), Expr::Binary { .. }));
+    }
+
+    #[test]
+    fn removes_dead_branches() {
+        let program =
+            optimize_source("if (1 > 2) {\n    print(1)\n}\nwhile (false) {\n    print(2)\n}");
+        assert!(program.stmts.is_empty());
+
+        let program = optimize_source(
+            "if (false) {\n    print(1)\n} elif (x) {\n    print(2)\n} else {\n    print(3)\n}",
+        );
+        assert!(matches!(
+            &program.stmts[0],
+            Stmt::Branch { cond: Expr::Var { name, .. }, else_if_branches, .. }
+                if name == "x" && else_if_branches.is_empty()
+        ));
+    }
+}
@@ -1,11 +1,17 @@
 // parser.rs
 
-use crate::ast::{BinOp, Expr, Function, Program, Stmt, Type};
+use crate::ast::{BinOp, Expr, Function, Program, Span, Stmt, Type, UnOp};
 use crate::lexer::{Lexer, Token};
+use std::panic;
+use std::rc::Rc;
 
 pub struct Parser {
     lexer: Lexer,
     current_token: Token,
+    /// Строка исходника, на которой начинается `current_token`.
+    current_line: usize,
+    /// Следующий токен (и его строка), если его уже подсмотрел `peek_token`.
+    peeked: Option<(Token, usize)>,
 }
 
 impl Parser {
@@ -14,21 +20,40 @@ impl Parser {
     pub fn new(mut lexer: Lexer) -> Self {
         let first = lexer.next_token();
         Self {
+            current_line: lexer.token_line(),
             lexer,
             current_token: first,
+            peeked: None,
         }
     }
 
+    /// Считать следующий токен из лексера вместе с его строкой.
+    fn lex_token(lexer: &mut Lexer) -> (Token, usize) {
+        let token = lexer.next_token();
+        (token, lexer.token_line())
+    }
+
     /// Сдвигаем текущий токен вперёд.
     fn bump(&mut self) {
-        self.current_token = self.lexer.next_token();
+        (self.current_token, self.current_line) = match self.peeked.take() {
+            Some(peeked) => peeked,
+            None => Self::lex_token(&mut self.lexer),
+        };
         // eprintln!("[DEBUG] bump -> token = {:?}", self.current_token);
     }
 
     /// Подглядеть следующий токен, не потребляя его.
-    fn peek_token(&mut self) -> Token {
-        let mut cloned_lexer = self.lexer.clone();
-        cloned_lexer.next_token()
+    /// Токен лексируется один раз и запоминается до следующего `bump`.
+    fn peek_token(&mut self) -> &Token {
+        let lexer = &mut self.lexer;
+        &self.peeked.get_or_insert_with(|| Self::lex_token(lexer)).0
+    }
+
+    /// Позиция текущего токена — для узлов AST, которые с него начинаются.
+    fn span(&self) -> Option<Span> {
+        Some(Span {
+            line: self.current_line,
+        })
     }
 
     /// Унифицированная функция ошибки парсера.
@@ -63,6 +88,10 @@ impl Parser {
                 self.bump();
                 Type::Int
             }
+            Token::Ident(name) if name == "float" => {
+                self.bump();
+                Type::Float
+            }
             Token::Ident(name) if name == "bool" => {
                 self.bump();
                 Type::Bool
@@ -71,17 +100,42 @@ impl Parser {
                 self.bump();
                 Type::Str
             }
+            Token::Ident(name) if name == "char" => {
+                self.bump();
+                Type::Char
+            }
             Token::Ident(name) if name == "list" => {
                 self.bump();
-                Type::List
+                // list[T] — список с типом элементов
+                if self.current_token == Token::LBracket {
+                    self.bump(); // съели '['
+                    let elem = self.parse_type();
+                    self.expect(Token::RBracket);
+                    Type::List(Some(Box::new(elem)))
+                } else {
+                    Type::List(None)
+                }
+            }
+            Token::Ident(name) if name == "dict" => {
+                self.bump();
+                Type::Dict
+            }
+            Token::Ident(name) if name == "tuple" => {
+                self.bump();
+                Type::Tuple
+            }
+            // `func` — ключевое слово, поэтому это не Ident
+            Token::KwFunc => {
+                self.bump();
+                Type::Func
             }
             other => self.error(&format!("expected type name, found {:?}", other)),
         }
     }
 
     /* ====================== ВЫРАЖЕНИЯ ====================== */
-    // Грамматика по приоритетам:
-    // primary -> factor -> term -> expr (пока без && и ||)
+    // Грамматика по приоритетам (от слабых к сильным):
+    // expr (or) -> and -> not -> comparison -> additive -> term -> unary -> factor -> primary
 
     fn parse_primary(&mut self) -> Expr {
         match &self.current_token {
@@ -90,11 +144,21 @@ impl Parser {
                 self.bump();
                 expr
             }
+            Token::FloatLiteral(value) => {
+                let expr = Expr::Float(*value);
+                self.bump();
+                expr
+            }
             Token::StrLiteral(s) => {
                 let expr = Expr::Str(s.clone());
                 self.bump();
                 expr
             }
+            Token::CharLiteral(c) => {
+                let expr = Expr::Char(*c);
+                self.bump();
+                expr
+            }
             Token::KwTrue => {
                 self.bump();
                 Expr::Bool(true)
@@ -104,20 +168,17 @@ impl Parser {
                 Expr::Bool(false)
             }
             Token::Ident(name) => {
-                let expr = Expr::Var(name.clone());
-                self.bump();
-                expr
-            }
-            Token::LParen => {
+                let expr = Expr::Var {
+                    name: name.clone(),
+                    span: self.span(),
+                };
                 self.bump();
-                let expr = self.parse_expr();
-                if self.current_token != Token::RParen {
-                    self.error("expected ')' after parenthesized expression");
-                }
-                self.bump(); // съели ')'
                 expr
             }
+            Token::LParen => self.parse_paren_or_tuple(),
             Token::LBracket => self.parse_list_literal(),
+            Token::LBrace => self.parse_dict_literal(),
+            Token::KwFn => self.parse_lambda(),
             other => self.error(&format!(
                 "unexpected token in primary expression: {:?}",
                 other
@@ -125,6 +186,39 @@ impl Parser {
         }
     }
 
+    /// `(expr)` — просто выражение в скобках; `()`, `(a,)`, `(a, b, ...)` — кортеж.
+    fn parse_paren_or_tuple(&mut self) -> Expr {
+        self.bump(); // съели '('
+        if self.current_token == Token::RParen {
+            self.bump();
+            return Expr::TupleLiteral(Vec::new());
+        }
+
+        let first = self.parse_expr();
+        if self.current_token != Token::Comma {
+            if self.current_token != Token::RParen {
+                self.error("expected ')' after parenthesized expression");
+            }
+            self.bump(); // съели ')'
+            return first;
+        }
+
+        let mut items = vec![first];
+        while self.current_token == Token::Comma {
+            self.bump(); // съели ','
+            if self.current_token == Token::RParen {
+                break; // запятая в конце: `(a,)`
+            }
+            items.push(self.parse_expr());
+        }
+        if self.current_token != Token::RParen {
+            self.error("expected ')' after tuple elements");
+        }
+        self.bump(); // съели ')'
+        Expr::TupleLiteral(items)
+    }
+
+    /// Постфиксные операции: вызовы `f(...)` и индексация `xs[i]`.
     fn parse_factor(&mut self) -> Expr {
         let mut node = self.parse_primary();
         loop {
@@ -132,22 +226,36 @@ impl Parser {
                 Token::LParen => {
                     node = self.parse_call(node);
                 }
+                Token::LBracket => {
+                    node = self.parse_index(node);
+                }
                 _ => break,
             }
         }
         node
     }
 
-    fn parse_call(&mut self, calle_expr: Expr) -> Expr {
-        let callee_name = match calle_expr {
-            Expr::Var(name) => name,
-            other => self.error(&format!(
-                "can only call functions by name, got expression: {:?}",
-                other
-            )),
-        };
+    fn parse_index(&mut self, target: Expr) -> Expr {
+        let span = self.span();
+        self.bump(); // съели '['
+        let index = self.parse_expr();
+        if self.current_token != Token::RBracket {
+            self.error("expected ']' after index expression");
+        }
+        self.bump(); // съели ']'
+
+        Expr::Index {
+            target: Box::new(target),
+            index: Box::new(index),
+            span,
+        }
+    }
 
+    /// Вызов `callee(...)`, где `callee` — уже разобранное выражение
+    /// (имя функции или, например, результат другого вызова).
+    fn parse_call(&mut self, callee: Expr) -> Expr {
         // сейчас current_token == LParen
+        let span = self.span();
         self.bump(); // съели '('
 
         let mut args: Vec<Expr> = Vec::new();
@@ -174,32 +282,52 @@ impl Parser {
         self.bump(); // съели ')'
 
         Expr::Call {
-            callee: callee_name,
+            callee: Box::new(callee),
             args,
+            span,
         }
     }
 
+    /// Унарный минус: `-x`, `-xs[0]`, `2 * -3`.
+    fn parse_unary(&mut self) -> Expr {
+        if self.current_token == Token::Minus {
+            let span = self.span();
+            self.bump();
+            let expr = self.parse_unary();
+            return Expr::Unary {
+                op: UnOp::Neg,
+                expr: Box::new(expr),
+                span,
+            };
+        }
+        self.parse_factor()
+    }
+
     fn parse_term(&mut self) -> Expr {
-        let mut node = self.parse_factor();
+        let mut node = self.parse_unary();
 
         loop {
             match self.current_token {
                 Token::Star => {
+                    let span = self.span();
                     self.bump();
-                    let rhs = self.parse_factor();
+                    let rhs = self.parse_unary();
                     node = Expr::Binary {
                         left: Box::new(node),
                         op: BinOp::Mul,
                         right: Box::new(rhs),
+                        span,
                     };
                 }
                 Token::Slash => {
+                    let span = self.span();
                     self.bump();
-                    let rhs = self.parse_factor();
+                    let rhs = self.parse_unary();
                     node = Expr::Binary {
                         left: Box::new(node),
                         op: BinOp::Div,
                         right: Box::new(rhs),
+                        span,
                     };
                 }
                 _ => break,
@@ -209,14 +337,72 @@ impl Parser {
         node
     }
 
-    /// Полное выражение: +, -, сравнения и т.п.
+    /// Полное выражение: самый низкий приоритет — `||` / `or`.
     pub fn parse_expr(&mut self) -> Expr {
-        let mut node = self.parse_term();
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Expr {
+        let mut node = self.parse_and();
+
+        while let Token::OrOr | Token::KwOr = self.current_token {
+            let span = self.span();
+            self.bump();
+            let rhs = self.parse_and();
+            node = Expr::Binary {
+                left: Box::new(node),
+                op: BinOp::Or,
+                right: Box::new(rhs),
+                span,
+            };
+        }
+
+        node
+    }
+
+    fn parse_and(&mut self) -> Expr {
+        let mut node = self.parse_not();
+
+        while let Token::AndAnd | Token::KwAnd = self.current_token {
+            let span = self.span();
+            self.bump();
+            let rhs = self.parse_not();
+            node = Expr::Binary {
+                left: Box::new(node),
+                op: BinOp::And,
+                right: Box::new(rhs),
+                span,
+            };
+        }
+
+        node
+    }
+
+    /// `!x` / `not x`. Как в Python, отрицание слабее сравнений:
+    /// `not a == b` означает `not (a == b)`.
+    fn parse_not(&mut self) -> Expr {
+        if let Token::Bang | Token::KwNot = self.current_token {
+            let span = self.span();
+            self.bump();
+            let expr = self.parse_not();
+            return Expr::Unary {
+                op: UnOp::Not,
+                expr: Box::new(expr),
+                span,
+            };
+        }
+        self.parse_comparison()
+    }
+
+    /// Сравнения. Цепочки вида `a < b <= c` разворачиваются в
+    /// `a < b && b <= c`, при этом каждый операнд вычисляется один раз.
+    fn parse_comparison(&mut self) -> Expr {
+        let first = self.parse_additive();
+        let span = self.span();
+        let mut rest: Vec<(BinOp, Expr)> = Vec::new();
 
         loop {
             let op = match self.current_token {
-                Token::Plus => BinOp::Add,
-                Token::Minus => BinOp::Sub,
                 Token::EqEq => BinOp::Eq,
                 Token::NotEq => BinOp::NotEq,
                 Token::Lt => BinOp::Lt,
@@ -226,6 +412,41 @@ impl Parser {
                 _ => break,
             };
 
+            self.bump();
+            rest.push((op, self.parse_additive()));
+        }
+
+        match rest.len() {
+            0 => first,
+            1 => {
+                let (op, rhs) = rest.pop().unwrap();
+                Expr::Binary {
+                    left: Box::new(first),
+                    op,
+                    right: Box::new(rhs),
+                    span,
+                }
+            }
+            _ => Expr::CompareChain {
+                first: Box::new(first),
+                rest,
+                span,
+            },
+        }
+    }
+
+    /// `+` и `-`.
+    fn parse_additive(&mut self) -> Expr {
+        let mut node = self.parse_term();
+
+        loop {
+            let op = match self.current_token {
+                Token::Plus => BinOp::Add,
+                Token::Minus => BinOp::Sub,
+                _ => break,
+            };
+
+            let span = self.span();
             self.bump();
             let rhs = self.parse_term();
 
@@ -233,6 +454,7 @@ impl Parser {
                 left: Box::new(node),
                 op,
                 right: Box::new(rhs),
+                span,
             };
         }
 
@@ -265,26 +487,80 @@ impl Parser {
         Expr::ListLiteral(items)
     }
 
+    /// `{key: value, ...}` — литерал словаря (`{}` — пустой словарь).
+    fn parse_dict_literal(&mut self) -> Expr {
+        self.bump(); // съели '{'
+
+        let mut pairs = Vec::new();
+
+        if self.current_token != Token::RBrace {
+            loop {
+                let key = self.parse_expr();
+                if self.current_token != Token::Colon {
+                    self.error("expected ':' between key and value in dict literal");
+                }
+                self.bump(); // съели ':'
+                let value = self.parse_expr();
+                pairs.push((key, value));
+
+                if self.current_token == Token::Comma {
+                    self.bump();
+                    continue;
+                }
+                break;
+            }
+        }
+
+        if self.current_token != Token::RBrace {
+            self.error("expected '}' at end of dict literal");
+        }
+        self.bump(); // съели '}'
+
+        Expr::DictLiteral(pairs)
+    }
+
     /* ===================== ОПЕРАТОРЫ ====================== */
 
     fn parse_var_decl(&mut self) -> Stmt {
+        let span = self.span();
         self.bump(); // съели 'var'
 
-        let name = match &self.current_token {
-            Token::Ident(n) => {
-                let s = n.clone();
-                self.bump();
-                s
-            }
-            other => self.error(&format!(
-                "expected identifier after 'var', found {:?}",
-                other
-            )),
+        let mutable = if self.current_token == Token::KwMut {
+            self.bump(); // съели 'mut'
+            true
+        } else {
+            false
         };
 
-        self.expect(Token::Colon);
+        let name = self.parse_var_name();
+
+        // `var a, b = ...` — деструктуризация (без аннотации типа)
+        if self.current_token == Token::Comma {
+            let mut names = vec![name];
+            while self.current_token == Token::Comma {
+                self.bump(); // съели ','
+                names.push(self.parse_var_name());
+            }
+            self.expect(Token::Eq);
+            let init = self.parse_expr();
+            if self.current_token == Token::Newline {
+                self.bump();
+            }
+            return Stmt::VarDestructure {
+                names,
+                mutable,
+                init,
+                span,
+            };
+        }
 
-        let ty = self.parse_type();
+        // аннотация типа необязательна: `var x = 5`
+        let ty = if self.current_token == Token::Colon {
+            self.bump(); // съели ':'
+            Some(self.parse_type())
+        } else {
+            None
+        };
 
         self.expect(Token::Eq);
 
@@ -294,21 +570,49 @@ impl Parser {
             self.bump();
         }
 
-        Stmt::VarDecl { name, ty, init }
+        Stmt::VarDecl {
+            name,
+            mutable,
+            ty,
+            init,
+            span,
+        }
     }
 
-    fn parse_assign_stmt(&mut self) -> Stmt {
-        let name = match &self.current_token {
+    /// Имя объявляемой переменной.
+    fn parse_var_name(&mut self) -> String {
+        match &self.current_token {
             Token::Ident(n) => {
                 let s = n.clone();
                 self.bump();
                 s
             }
-            other => self.error(&format!(
-                "expected identifier at start of assignment, found {:?}",
-                other
-            )),
-        };
+            other => self.error(&match other.keyword_text() {
+                Some(kw) => format!("'{}' is a reserved word and cannot be used as a name", kw),
+                None => format!("expected identifier after 'var', found {:?}", other),
+            }),
+        }
+    }
+
+    fn parse_assign_stmt(&mut self) -> Stmt {
+        let span = self.span();
+        let mut names = Vec::new();
+        loop {
+            match &self.current_token {
+                Token::Ident(n) => {
+                    names.push(n.clone());
+                    self.bump();
+                }
+                other => self.error(&format!(
+                    "expected identifier at start of assignment, found {:?}",
+                    other
+                )),
+            }
+            if self.current_token != Token::Comma {
+                break;
+            }
+            self.bump(); // съели ','
+        }
 
         self.expect(Token::Eq);
 
@@ -318,7 +622,12 @@ impl Parser {
             self.bump();
         }
 
-        Stmt::Assign { name, expr }
+        if names.len() == 1 {
+            let name = names.pop().unwrap();
+            Stmt::Assign { name, expr, span }
+        } else {
+            Stmt::AssignDestructure { names, expr, span }
+        }
     }
 
     fn parse_return_stmt(&mut self) -> Stmt {
@@ -382,8 +691,7 @@ impl Parser {
 
         let else_branch = if self.current_token == Token::KwElse {
             self.bump(); // съели 'else'
-            let block = self.parse_block();
-            block
+            self.parse_block()
         } else {
             Vec::new()
         };
@@ -400,31 +708,181 @@ impl Parser {
         self.bump(); // съели 'while'
         let cond = self.parse_expr();
         let body = self.parse_block();
+        let else_branch = self.parse_loop_else();
+
+        Stmt::While {
+            cond,
+            body,
+            else_branch,
+        }
+    }
+
+    fn parse_do_while_stmt(&mut self) -> Stmt {
+        self.bump(); // съели 'do'
+        let body = self.parse_block();
+
+        self.skip_newlines();
+        if self.current_token != Token::KwWhile {
+            self.error("expected 'while' after the body of 'do { ... }'");
+        }
+        self.bump(); // съели 'while'
+        let cond = self.parse_expr();
+        if self.current_token == Token::Newline {
+            self.bump();
+        }
+
+        Stmt::DoWhile { body, cond }
+    }
 
-        Stmt::While { cond, body }
+    fn parse_try_stmt(&mut self) -> Stmt {
+        self.bump(); // съели 'try'
+        let body = self.parse_block();
+
+        self.skip_newlines();
+        if self.current_token != Token::KwCatch {
+            self.error("expected 'catch' after the body of 'try { ... }'");
+        }
+        self.bump(); // съели 'catch'
+
+        let err_name = match &self.current_token {
+            Token::Ident(name) => name.clone(),
+            other => self.error(&format!(
+                "expected error variable name after 'catch', found {:?}",
+                other
+            )),
+        };
+        self.bump();
+        let handler = self.parse_block();
+
+        Stmt::Try {
+            body,
+            err_name,
+            handler,
+        }
+    }
+
+    fn parse_global_stmt(&mut self) -> Stmt {
+        self.bump(); // съели 'global'
+
+        let mut names = Vec::new();
+        loop {
+            match &self.current_token {
+                Token::Ident(name) => names.push(name.clone()),
+                other => self.error(&format!(
+                    "expected variable name after 'global', found {:?}",
+                    other
+                )),
+            }
+            self.bump();
+            if self.current_token != Token::Comma {
+                break;
+            }
+            self.bump(); // съели ','
+        }
+        if self.current_token == Token::Newline {
+            self.bump();
+        }
+
+        Stmt::Global(names)
+    }
+
+    fn parse_repeat_stmt(&mut self) -> Stmt {
+        self.bump(); // съели 'repeat'
+        let count = self.parse_expr();
+        let body = self.parse_block();
+
+        Stmt::Repeat { count, body }
+    }
+
+    fn parse_loop_stmt(&mut self) -> Stmt {
+        self.bump(); // съели 'loop'
+        let body = self.parse_block();
+
+        Stmt::Loop { body }
+    }
+
+    /// Необязательный `else { ... }` сразу после тела цикла.
+    fn parse_loop_else(&mut self) -> Vec<Stmt> {
+        self.skip_newlines();
+        if self.current_token == Token::KwElse {
+            self.bump(); // съели 'else'
+            self.parse_block()
+        } else {
+            Vec::new()
+        }
     }
 
     fn parse_for_stmt(&mut self) -> Stmt {
         self.bump(); // съели 'for'
 
         match &self.current_token {
-            // ---------- foreach: for x in xs { ... } ----------
+            // ---------- foreach: for x in xs { ... } / for k, v in d { ... } ----------
             Token::Ident(name) => {
                 let var_name = name.clone();
                 self.bump(); // съели имя
 
+                let value_name = if self.current_token == Token::Comma {
+                    self.bump(); // съели ','
+                    match &self.current_token {
+                        Token::Ident(name) => {
+                            let name = name.clone();
+                            self.bump();
+                            Some(name)
+                        }
+                        other => self.error(&format!(
+                            "expected second loop variable after ',', found {:?}",
+                            other
+                        )),
+                    }
+                } else {
+                    None
+                };
+
                 if self.current_token != Token::KwIn {
                     self.error("invalid foreach statement: expected 'in'");
                 }
                 self.bump(); // съели 'in'
 
                 let iter_expr = self.parse_expr();
+
+                // диапазон: for i in a..b / for i in a..b step k
+                if self.current_token == Token::DotDot {
+                    if value_name.is_some() {
+                        self.error("a range loop takes exactly one loop variable");
+                    }
+                    self.bump(); // съели '..'
+                    let end = self.parse_expr();
+                    // `step` — не ключевое слово: так его можно по-прежнему
+                    // использовать как имя переменной
+                    let step = match &self.current_token {
+                        Token::Ident(word) if word == "step" => {
+                            self.bump(); // съели 'step'
+                            Some(self.parse_expr())
+                        }
+                        _ => None,
+                    };
+                    let body = self.parse_block();
+                    let else_branch = self.parse_loop_else();
+
+                    return Stmt::ForRange {
+                        var_name,
+                        start: iter_expr,
+                        end,
+                        step,
+                        body,
+                        else_branch,
+                    };
+                }
+
                 let body = self.parse_block();
+                let else_branch = self.parse_loop_else();
 
                 Stmt::ForEach {
                     var_name,
+                    value_name,
                     iter_expr,
                     body,
+                    else_branch,
                 }
             }
 
@@ -469,12 +927,14 @@ impl Parser {
 
                 // тело — обычный блок { ... }
                 let body = self.parse_block();
+                let else_branch = self.parse_loop_else();
 
                 Stmt::For {
                     init,
                     cond,
                     step,
                     body,
+                    else_branch,
                 }
             }
 
@@ -482,29 +942,92 @@ impl Parser {
         }
     }
 
+    fn parse_match_stmt(&mut self) -> Stmt {
+        self.bump(); // съели 'match'
+
+        let scrutinee = self.parse_expr();
+
+        self.expect(Token::LBrace);
+        self.skip_newlines();
+
+        let mut arms: Vec<(Expr, Vec<Stmt>)> = Vec::new();
+        let mut default: Vec<Stmt> = Vec::new();
+
+        while self.current_token != Token::RBrace {
+            if self.current_token == Token::KwElse {
+                self.bump(); // съели 'else'
+                default = self.parse_block();
+                self.skip_newlines();
+                // `else` — всегда последняя ветка
+                break;
+            }
+
+            // образец — только литерал
+            let pattern = match &self.current_token {
+                Token::IntLiteral(n) => Expr::Int(*n),
+                Token::StrLiteral(s) => Expr::Str(s.clone()),
+                Token::CharLiteral(c) => Expr::Char(*c),
+                Token::KwTrue => Expr::Bool(true),
+                Token::KwFalse => Expr::Bool(false),
+                other => self.error(&format!(
+                    "match pattern must be an int, bool, str or char literal, found {:?}",
+                    other
+                )),
+            };
+            self.bump();
+
+            let body = self.parse_block();
+            arms.push((pattern, body));
+            self.skip_newlines();
+        }
+
+        self.expect(Token::RBrace);
+
+        Stmt::Match {
+            scrutinee,
+            arms,
+            default,
+        }
+    }
+
     fn parse_stmt(&mut self) -> Stmt {
         // eprintln!("[DEBUG] parse_stmt: current_token = {:?}", self.current_token);
 
+        // присваивание: `name = ...` / `a, b = ...`; иначе — см. match ниже
+        if matches!(self.current_token, Token::Ident(_))
+            && matches!(self.peek_token(), Token::Eq | Token::Comma)
+        {
+            return self.parse_assign_stmt();
+        }
+
         match self.current_token {
             Token::Kwvar => self.parse_var_decl(),
             Token::KwIf => self.parse_if_stmt(),
             Token::KwWhile => self.parse_while_stmt(),
+            Token::KwDo => self.parse_do_while_stmt(),
+            Token::KwRepeat => self.parse_repeat_stmt(),
+            Token::KwLoop => self.parse_loop_stmt(),
             Token::KwFor => self.parse_for_stmt(),
+            Token::KwMatch => self.parse_match_stmt(),
+            Token::KwTry => self.parse_try_stmt(),
+            Token::KwGlobal => self.parse_global_stmt(),
+            Token::KwFunc => Stmt::FuncDecl(self.parse_function()),
+            Token::KwImport => self.error("'import' is only allowed at the top level of a program"),
             Token::KwReturn => self.parse_return_stmt(),
-
-            Token::Ident(_) => {
-                // либо присваивание, либо выражение / вызов
-                if self.peek_token() == Token::Eq {
-                    self.parse_assign_stmt()
+            Token::KwBreak | Token::KwContinue => {
+                let stmt = if self.current_token == Token::KwBreak {
+                    Stmt::Break
                 } else {
-                    let expr = self.parse_expr();
-                    if self.current_token == Token::Newline {
-                        self.bump();
-                    }
-                    Stmt::ExprStmt(expr)
+                    Stmt::Continue
+                };
+                self.bump();
+                if self.current_token == Token::Newline {
+                    self.bump();
                 }
+                stmt
             }
 
+            // выражение / вызов
             _ => {
                 let expr = self.parse_expr();
                 if self.current_token == Token::Newline {
@@ -532,6 +1055,46 @@ impl Parser {
             )),
         };
 
+        let params = self.parse_params();
+        let ret_type = self.parse_ret_type();
+        let body = self.parse_block();
+
+        Function {
+            name,
+            params,
+            ret_type,
+            body,
+        }
+    }
+
+    /// Анонимная функция `fn(p1: T1, ...) { ... }`.
+    fn parse_lambda(&mut self) -> Expr {
+        self.bump(); // съели 'fn'
+
+        let params = self.parse_params();
+        let ret_type = self.parse_ret_type();
+        let body = self.parse_block();
+
+        Expr::Lambda(Rc::new(Function {
+            name: "lambda".to_string(),
+            params,
+            ret_type,
+            body,
+        }))
+    }
+
+    /// Необязательный тип результата после параметров: `-> int`.
+    fn parse_ret_type(&mut self) -> Option<Type> {
+        if self.current_token == Token::Arrow {
+            self.bump(); // съели '->'
+            Some(self.parse_type())
+        } else {
+            None
+        }
+    }
+
+    /// Список параметров в скобках: `(p1: T1, p2: T2, ...)`.
+    fn parse_params(&mut self) -> Vec<(String, Type)> {
         self.expect(Token::LParen);
 
         let mut params: Vec<(String, Type)> = Vec::new();
@@ -544,7 +1107,12 @@ impl Parser {
                         self.bump();
                         s
                     }
-                    other => self.error(&format!("expected parameter name, found {:?}", other)),
+                    other => self.error(&match other.keyword_text() {
+                        Some(kw) => {
+                            format!("'{}' is a reserved word and cannot be used as a name", kw)
+                        }
+                        None => format!("expected parameter name, found {:?}", other),
+                    }),
                 };
 
                 self.expect(Token::Colon);
@@ -563,13 +1131,11 @@ impl Parser {
         }
 
         self.expect(Token::RParen);
-
-        let body = self.parse_block();
-
-        Function { name, params, body }
+        params
     }
 
     pub fn parse_program(&mut self) -> Program {
+        let mut imports: Vec<String> = Vec::new();
         let mut functions: Vec<Function> = Vec::new();
         let mut stmts: Vec<Stmt> = Vec::new();
 
@@ -581,6 +1147,7 @@ impl Parser {
                     let func = self.parse_function();
                     functions.push(func);
                 }
+                Token::KwImport => imports.push(self.parse_import()),
                 _ => {
                     let stmt = self.parse_stmt();
                     stmts.push(stmt);
@@ -589,6 +1156,47 @@ impl Parser {
             self.skip_newlines();
         }
 
-        Program { functions, stmts }
+        Program {
+            imports,
+            functions,
+            stmts,
+        }
+    }
+
+    /// `import "path.rht"` — только на верхнем уровне программы.
+    fn parse_import(&mut self) -> String {
+        self.bump(); // съели 'import'
+        let path = match &self.current_token {
+            Token::StrLiteral(path) => path.clone(),
+            other => self.error(&format!(
+                "expected file path string after 'import', found {:?}",
+                other
+            )),
+        };
+        self.bump();
+        if self.current_token == Token::Newline {
+            self.bump();
+        }
+        path
     }
 }
+
+/// Разобрать текст программы, не роняя процесс: лексер и парсер сообщают
+/// об ошибке через `panic!`, здесь её текст возвращается как `Err`.
+/// Нужно там, где исходник приходит не из командной строки — например, для `import`.
+pub fn try_parse_program(text: &str) -> Result<Program, String> {
+    // пока идёт разбор, стандартный обработчик не печатает отчёт о панике:
+    // ошибку покажет тот, кто получит `Err`
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(|| Parser::new(Lexer::new(text)).parse_program());
+    panic::set_hook(hook);
+
+    result.map_err(|payload| match payload.downcast::<String>() {
+        Ok(msg) => *msg,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(msg) => msg.to_string(),
+            Err(_) => "parse error".to_string(),
+        },
+    })
+}
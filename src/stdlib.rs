@@ -1,26 +1,150 @@
-use crate::interpreter::Value;
+use crate::interpreter::{Dict, RuntimeError, Value, error};
+use std::cmp::Ordering;
+use std::io::{self, Write};
+use std::num::IntErrorKind;
+use std::rc::Rc;
+
+/// Имена всех функций из `call_builtin` — для подсказок "did you mean"
+/// при опечатке в имени. Добавляя функцию, добавьте и её имя сюда.
+pub const BUILTIN_NAMES: &[&str] = &[
+    "print",
+    "printn",
+    "eprintln",
+    "eprint",
+    "debug",
+    "print_sep",
+    "len",
+    "is_empty",
+    "contains",
+    "index_of",
+    "range",
+    "enumerate",
+    "zip",
+    "slice",
+    "push",
+    "pop",
+    "extend",
+    "take",
+    "drop",
+    "remove_at",
+    "get",
+    "set",
+    "contains_key",
+    "head",
+    "tail",
+    "chars",
+    "bytes",
+    "words",
+    "split_whitespace",
+    "to_list",
+    "ord",
+    "chr",
+    "round",
+    "format_float",
+    "pad_left",
+    "pad_right",
+    "center",
+    "join_lines",
+    "build_string",
+    "format_table",
+    "clamp",
+    "abs",
+    "abs_diff",
+    "divmod",
+    "add_sat",
+    "sub_sat",
+    "argmax",
+    "argmin",
+    "all",
+    "any",
+    "first",
+    "last",
+    "copy",
+    "str",
+    "bool",
+    "int",
+    "float",
+    "int_or",
+    "float_or",
+    "parse_int",
+    "parse_float",
+    "exit",
+    "raise",
+    "assert_eq",
+];
 
 /// Встроенные функции языка.
-/// Если имя совпадает с одной из функций ниже — возвращаем Some(Value),
-/// иначе None (значит, нужно искать пользовательскую функцию).
-pub fn call_builtin(name: &str, args: &Vec<Value>) -> Option<Value> {
-    match name {
+/// Если имя совпадает с одной из функций ниже — возвращаем Ok(Some(Value)),
+/// иначе Ok(None) (значит, нужно искать пользовательскую функцию).
+/// Неверные аргументы — `Err(RuntimeError)`.
+/// Весь вывод (`print` и его варианты) идёт в `out`, а не напрямую в stdout.
+pub fn call_builtin(
+    name: &str,
+    args: &[Value],
+    out: &mut dyn Write,
+) -> Result<Option<Value>, RuntimeError> {
+    let result = match name {
         // --------------------------
         // print(x, y, z, ...)
-        // Печатает значения через пробел и возвращает Unit.
+        // Печатает значения через пробел, в конце — перевод строки.
         // --------------------------
         "print" => {
-            let mut first = true;
+            print_values(out, args, " ", "\n")?;
+            Value::Unit
+        }
+
+        // --------------------------
+        // printn(x, y, z, ...)
+        // Как print, но без перевода строки в конце.
+        // --------------------------
+        "printn" => {
+            print_values(out, args, " ", "")?;
+            Value::Unit
+        }
+
+        // --------------------------
+        // eprintln(x, y, ...) / eprint(x, y, ...)
+        // Как print / printn, но в stderr — для диагностики,
+        // которая не должна смешиваться с выводом программы.
+        // --------------------------
+        "eprintln" => {
+            print_values(&mut io::stderr(), args, " ", "\n")?;
+            Value::Unit
+        }
+        "eprint" => {
+            print_values(&mut io::stderr(), args, " ", "")?;
+            Value::Unit
+        }
 
-            for v in args {
-                if !first {
-                    print!(" ");
+        // --------------------------
+        // debug(x) -> x
+        // Печатает значение в stderr в отладочном виде, где виден тип
+        // (`Int(3)`, `Str("3")`), и возвращает его как есть — поэтому
+        // вызов можно вставить прямо в выражение:
+        //
+        //   var y: int = debug(compute())
+        // --------------------------
+        "debug" => match args {
+            [value] => {
+                if let Err(e) = writeln!(io::stderr(), "{:?}", value) {
+                    return error(format!("failed to write output: {}", e));
                 }
-                first = false;
-                print_value(v);
+                value.clone()
             }
-            println!();
-            Some(Value::Unit)
+            _ => return error("debug(x) expects exactly 1 argument"),
+        },
+
+        // --------------------------
+        // print_sep(sep, end, x, y, ...)
+        // Печатает значения через `sep` и дописывает `end`.
+        // --------------------------
+        "print_sep" => {
+            let (sep, end, values) = match args {
+                [Value::Str(sep), Value::Str(end), values @ ..] => (sep, end, values),
+                _ => return error("print_sep(sep, end, ...) expects sep and end to be strings"),
+            };
+            print_values(out, values, sep, end)?;
+            Value::Unit
         }
 
         // --------------------------
@@ -30,15 +154,97 @@ pub fn call_builtin(name: &str, args: &Vec<Value>) -> Option<Value> {
         // --------------------------
         "len" => {
             if args.len() != 1 {
-                panic!("len(x) expects exactly 1 argument");
+                return error("len(x) expects exactly 1 argument");
             }
             let v = &args[0];
             let n = match v {
                 Value::Str(s) => s.chars().count() as i64,
                 Value::List(items) => items.len() as i64,
-                other => panic!("len(...) is not defined for value {:?}", other),
+                Value::Dict(dict) => dict.len() as i64,
+                Value::Tuple(items) => items.len() as i64,
+                other => return error(format!("len(...) is not defined for value {:?}", other)),
+            };
+            Value::Int(n)
+        }
+
+        // --------------------------
+        // is_empty(x)
+        // Строка / список / словарь / кортеж -> пуст ли он
+        // --------------------------
+        "is_empty" => {
+            if args.len() != 1 {
+                return error("is_empty(x) expects exactly 1 argument");
+            }
+            let empty = match &args[0] {
+                Value::Str(s) => s.is_empty(),
+                Value::List(items) => items.is_empty(),
+                Value::Dict(dict) => dict.len() == 0,
+                Value::Tuple(items) => items.is_empty(),
+                other => {
+                    return error(format!(
+                        "is_empty(...) is not defined for value {:?}",
+                        other
+                    ));
+                }
+            };
+            Value::Bool(empty)
+        }
+
+        // --------------------------
+        // contains(x, item)
+        // Список -> есть ли элемент, равный item
+        // Строка -> есть ли подстрока item
+        // Словарь -> есть ли ключ item
+        // --------------------------
+        "contains" => {
+            let (container, item) = match args {
+                [container, item] => (container, item),
+                _ => return error("contains(x, item) expects exactly 2 arguments"),
+            };
+            let found = match (container, item) {
+                (Value::List(items), item) => items.iter().any(|v| v == item),
+                (Value::Str(s), Value::Str(sub)) => s.contains(sub.as_str()),
+                (Value::Str(s), Value::Char(c)) => s.contains(*c),
+                (Value::Dict(dict), key) => dict.contains_key(key)?,
+                (Value::Str(_), other) => {
+                    return error(format!(
+                        "contains(str, item): item must be str or char, got {:?}",
+                        other
+                    ));
+                }
+                (other, _) => {
+                    return error(format!(
+                        "contains(x, item): x must be list, str or dict, got {:?}",
+                        other
+                    ));
+                }
+            };
+            Value::Bool(found)
+        }
+
+        // --------------------------
+        // index_of(x, item)
+        // Индекс первого элемента списка, равного item (или первой подстроки
+        // item в строке, в символах); -1, если не найдено.
+        // --------------------------
+        "index_of" => {
+            let (container, item) = match args {
+                [container, item] => (container, item),
+                _ => return error("index_of(x, item) expects exactly 2 arguments"),
+            };
+            let pos = match (container, item) {
+                (Value::List(items), item) => items.iter().position(|v| v == item),
+                (Value::Str(s), Value::Str(sub)) => s
+                    .find(sub.as_str())
+                    .map(|byte_pos| s[..byte_pos].chars().count()),
+                (other, _) => {
+                    return error(format!(
+                        "index_of(x, item): expected list or (str, str), got {:?}",
+                        other
+                    ));
+                }
             };
-            Some(Value::Int(n))
+            Value::Int(pos.map_or(-1, |p| p as i64))
         }
 
         // --------------------------
@@ -46,21 +252,119 @@ pub fn call_builtin(name: &str, args: &Vec<Value>) -> Option<Value> {
         // Создаёт список [0, 1, ..., n-1]
         // --------------------------
         "range" => {
+            let n = range_len(args)?;
+            Value::list((0..n).map(Value::Int).collect())
+        }
+
+        // --------------------------
+        // enumerate(list)
+        // Пары [индекс, элемент]:
+        //   enumerate(["a", "b"]) -> [[0, "a"], [1, "b"]]
+        // --------------------------
+        "enumerate" => {
             if args.len() != 1 {
-                panic!("range(n) expects exactly 1 argument");
+                return error("enumerate(list) expects exactly 1 argument");
             }
-            let n = match args[0] {
-                Value::Int(n) => n,
-                ref other => panic!("range(n): n must be int, got {:?}", other),
-            };
-            if n < 0 {
-                panic!("range(n): n must be >= 0");
+            match &args[0] {
+                Value::List(items) => {
+                    let pairs = items
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| Value::list(vec![Value::Int(i as i64), v.clone()]))
+                        .collect();
+                    Value::list(pairs)
+                }
+                other => {
+                    return error(format!(
+                        "enumerate(list): argument must be list, got {:?}",
+                        other
+                    ));
+                }
             }
-            let mut items = Vec::new();
-            for i in 0..n {
-                items.push(Value::Int(i));
+        }
+
+        // --------------------------
+        // zip(a, b, ...)
+        // Списки из элементов с одинаковыми индексами,
+        // длина — по самому короткому списку:
+        //   zip([1, 2, 3], ["a", "b"]) -> [[1, "a"], [2, "b"]]
+        // --------------------------
+        "zip" => {
+            if args.len() < 2 {
+                return error("zip(a, b, ...) expects at least 2 arguments");
+            }
+            let mut lists = Vec::new();
+            for (i, arg) in args.iter().enumerate() {
+                match arg {
+                    Value::List(items) => lists.push(items),
+                    other => {
+                        return error(format!(
+                            "zip(...): argument {} must be list, got {:?}",
+                            i + 1,
+                            other
+                        ));
+                    }
+                }
+            }
+            let len = lists.iter().map(|items| items.len()).min().unwrap_or(0);
+            let rows = (0..len)
+                .map(|i| Value::list(lists.iter().map(|items| items[i].clone()).collect()))
+                .collect();
+            Value::list(rows)
+        }
+
+        // --------------------------
+        // slice(x, start, end)
+        // Элементы списка (или символы строки) с индексами start..end.
+        // Отрицательные границы считаются с конца, как у индексации:
+        //   slice([1, 2, 3, 4], 1, -1) -> [2, 3]
+        // --------------------------
+        "slice" => {
+            if args.len() != 3 {
+                return error("slice(x, start, end) expects exactly 3 arguments");
+            }
+            let (start, end) = match (&args[1], &args[2]) {
+                (Value::Int(start), Value::Int(end)) => (*start, *end),
+                (a, b) => {
+                    return error(format!(
+                        "slice(x, start, end): bounds must be int, got {:?} and {:?}",
+                        a, b
+                    ));
+                }
+            };
+            let bounds = |len: usize| {
+                // конец может указывать сразу за последний элемент
+                let resolve = |i: i64| {
+                    if i == len as i64 {
+                        Some(len)
+                    } else {
+                        resolve_index(i, len)
+                    }
+                };
+                match (resolve(start), resolve(end)) {
+                    (Some(from), Some(to)) if from <= to => Ok((from, to)),
+                    _ => error(format!(
+                        "slice(x, {}, {}): bounds out of range for length {}",
+                        start, end, len
+                    )),
+                }
+            };
+            match &args[0] {
+                Value::List(items) => {
+                    let (from, to) = bounds(items.len())?;
+                    Value::list(items[from..to].to_vec())
+                }
+                Value::Str(s) => {
+                    let (from, to) = bounds(s.chars().count())?;
+                    Value::Str(s.chars().skip(from).take(to - from).collect())
+                }
+                other => {
+                    return error(format!(
+                        "slice(x, start, end): x must be list or str, got {:?}",
+                        other
+                    ));
+                }
             }
-            Some(Value::List(items))
         }
 
         // --------------------------
@@ -71,33 +375,170 @@ pub fn call_builtin(name: &str, args: &Vec<Value>) -> Option<Value> {
         // --------------------------
         "push" => {
             if args.len() != 2 {
-                panic!("push(list, value) expects exactly 2 arguments");
+                return error("push(list, value) expects exactly 2 arguments");
             }
             let list = match &args[0] {
-                Value::List(items) => items.clone(),
-                other => panic!("push(list, value): first arg must be list, got {:?}", other),
+                Value::List(items) => items.to_vec(),
+                other => {
+                    return error(format!(
+                        "push(list, value): first arg must be list, got {:?}",
+                        other
+                    ));
+                }
             };
             let mut new_list = list;
             new_list.push(args[1].clone());
-            Some(Value::List(new_list))
+            Value::list(new_list)
         }
 
+        // --------------------------
+        // pop(list) -> (последний элемент, список без него)
+        // --------------------------
+        "pop" => match args {
+            [Value::List(items)] => match items.split_last() {
+                Some((last, rest)) => {
+                    Value::Tuple(Rc::new(vec![last.clone(), Value::list(rest.to_vec())]))
+                }
+                None => return error("pop([]): empty list"),
+            },
+            _ => return error("pop(list) expects exactly 1 list argument"),
+        },
+
+        // --------------------------
+        // extend(list, other)
+        // Возвращает НОВЫЙ список: элементы list, затем элементы other.
+        //
+        //   xs = extend(xs, [4, 5])
+        // --------------------------
+        "extend" => match args {
+            [Value::List(items), Value::List(other)] => {
+                Value::list(items.iter().chain(other.iter()).cloned().collect())
+            }
+            [_, _] => {
+                return error(format!(
+                    "extend(list, other): both args must be lists, got {} and {}",
+                    describe_value(&args[0]),
+                    describe_value(&args[1])
+                ));
+            }
+            _ => return error("extend(list, other) expects exactly 2 arguments"),
+        },
+
+        // --------------------------
+        // take(list, n) -> первые n элементов (весь список, если n больше длины)
+        // drop(list, n) -> всё, кроме первых n элементов ([], если n больше длины)
+        // Отрицательное n — ошибка.
+        // --------------------------
+        "take" | "drop" => match args {
+            [Value::List(items), Value::Int(n)] => {
+                if *n < 0 {
+                    return error(format!("{}(list, n): n must be >= 0, got {}", name, n));
+                }
+                let n = usize::try_from(*n).unwrap_or(usize::MAX).min(items.len());
+                let (head, rest) = items.split_at(n);
+                Value::list(if name == "take" { head } else { rest }.to_vec())
+            }
+            _ => return error(format!("{}(list, n) expects a list and an int", name)),
+        },
+
+        // --------------------------
+        // remove_at(list, i)
+        // Возвращает НОВЫЙ список без элемента с индексом i
+        // (отрицательный индекс — с конца, как при индексации).
+        // --------------------------
+        "remove_at" => match args {
+            [Value::List(items), Value::Int(i)] => match resolve_index(*i, items.len()) {
+                Some(index) => {
+                    let mut new_list = items.to_vec();
+                    new_list.remove(index);
+                    Value::list(new_list)
+                }
+                None => {
+                    return error(format!(
+                        "remove_at(list, i): index {} out of range for list of length {}",
+                        i,
+                        items.len()
+                    ));
+                }
+            },
+            [_, _] => {
+                return error(format!(
+                    "remove_at(list, i) expects a list and an int, got {} and {}",
+                    describe_value(&args[0]),
+                    describe_value(&args[1])
+                ));
+            }
+            _ => return error("remove_at(list, i) expects exactly 2 arguments"),
+        },
+
+        // --------------------------
+        // get(dict, key, default) -> значение по ключу или default, если ключа нет.
+        // Не падает и на ключе, который не может быть в словаре (например, из словаря).
+        // --------------------------
+        "get" => match args {
+            [Value::Dict(dict), key, default] => match dict.get(key) {
+                Ok(Some(value)) => value.clone(),
+                Ok(None) | Err(_) => default.clone(),
+            },
+            [other, _, _] => {
+                return error(format!(
+                    "get(dict, key, default): first arg must be dict, got {:?}",
+                    other
+                ));
+            }
+            _ => return error("get(dict, key, default) expects exactly 3 arguments"),
+        },
+
+        // --------------------------
+        // set(dict, key, value)
+        // Возвращает НОВЫЙ словарь с key = value (как push для списков):
+        //
+        //   counts = set(counts, w, get(counts, w, 0) + 1)
+        // --------------------------
+        "set" => match args {
+            [Value::Dict(dict), key, value] => {
+                let mut new_dict = Dict::clone(dict);
+                new_dict.insert(key.clone(), value.clone())?;
+                Value::Dict(Rc::new(new_dict))
+            }
+            [other, _, _] => {
+                return error(format!(
+                    "set(dict, key, value): first arg must be dict, got {:?}",
+                    other
+                ));
+            }
+            _ => return error("set(dict, key, value) expects exactly 3 arguments"),
+        },
+
+        // --------------------------
+        // contains_key(dict, key) -> есть ли ключ (то же, что contains для словаря)
+        // --------------------------
+        "contains_key" => match args {
+            [Value::Dict(dict), key] => Value::Bool(dict.contains_key(key)?),
+            _ => return error("contains_key(dict, key) expects a dict and a key"),
+        },
+
         // --------------------------
         // head(list)
         // Первый элемент списка.
         // --------------------------
         "head" => {
             if args.len() != 1 {
-                panic!("head(list) expects exactly 1 argument");
+                return error("head(list) expects exactly 1 argument");
             }
             match &args[0] {
                 Value::List(items) => {
                     if items.is_empty() {
-                        panic!("head([]): empty list");
+                        return error("head([]): empty list");
                     }
-                    Some(items[0].clone())
+                    items[0].clone()
+                }
+                other => {
+                    return error(format!(
+                        "head(list): argument must be list, got {:?}",
+                        other
+                    ));
                 }
-                other => panic!("head(list): argument must be list, got {:?}", other),
             }
         }
 
@@ -107,108 +548,895 @@ pub fn call_builtin(name: &str, args: &Vec<Value>) -> Option<Value> {
         // --------------------------
         "tail" => {
             if args.len() != 1 {
-                panic!("tail(list) expects exactly 1 argument");
+                return error("tail(list) expects exactly 1 argument");
             }
             match &args[0] {
                 Value::List(items) => {
                     if items.is_empty() {
-                        panic!("tail([]): empty list");
+                        return error("tail([]): empty list");
                     }
                     let tail_slice = &items[1..];
-                    Some(Value::List(tail_slice.to_vec()))
+                    Value::list(tail_slice.to_vec())
+                }
+                other => {
+                    return error(format!(
+                        "tail(list): argument must be list, got {:?}",
+                        other
+                    ));
+                }
+            }
+        }
+
+        // --------------------------
+        // chars(s) -> список символов (char)
+        // bytes(s) -> список байтов UTF-8 (int)
+        // --------------------------
+        "chars" => match args {
+            [Value::Str(s)] => Value::list(s.chars().map(Value::Char).collect()),
+            _ => return error("chars(s) expects exactly 1 str argument"),
+        },
+        "bytes" => match args {
+            [Value::Str(s)] => Value::list(s.bytes().map(|b| Value::Int(b as i64)).collect()),
+            _ => return error("bytes(s) expects exactly 1 str argument"),
+        },
+
+        // --------------------------
+        // words(s) / split_whitespace(s) -> слова строки: разбиение по любым
+        // пробельным символам (пробелы, табы, переводы строк), без пустых кусков
+        //   words("  a   b \tc ") -> ["a", "b", "c"]
+        // --------------------------
+        "words" | "split_whitespace" => match args {
+            [Value::Str(s)] => Value::list(
+                s.split_whitespace()
+                    .map(|word| Value::Str(word.to_string()))
+                    .collect(),
+            ),
+            _ => return error(format!("{}(s) expects exactly 1 str argument", name)),
+        },
+
+        // --------------------------
+        // to_list(x)
+        // То же, что обходит `for v in x`, но списком:
+        //   int n -> [0, 1, ..., n-1]
+        //   str   -> символы
+        //   list  -> как есть
+        //   tuple -> его элементы
+        //   dict  -> ключи в порядке вставки
+        // --------------------------
+        "to_list" => match args {
+            [Value::Int(n)] if *n >= 0 => Value::list((0..*n).map(Value::Int).collect()),
+            [Value::Str(s)] => Value::list(s.chars().map(Value::Char).collect()),
+            [list @ Value::List(_)] => list.clone(),
+            [Value::Tuple(items)] => Value::list(items.to_vec()),
+            [Value::Dict(dict)] => Value::list(dict.iter().map(|(key, _)| key.clone()).collect()),
+            [other] => return error(format!("to_list(x) cannot convert {:?} to a list", other)),
+            _ => return error("to_list(x) expects exactly 1 argument"),
+        },
+
+        // --------------------------
+        // ord(c) -> код символа (Unicode code point)
+        // Принимает char (например, `s[i]`) или строку из одного символа.
+        // --------------------------
+        "ord" => {
+            let c = match args {
+                [Value::Char(c)] => *c,
+                [Value::Str(s)] if s.chars().count() == 1 => s.chars().next().unwrap(),
+                _ => return error("ord(c) expects exactly 1 char argument"),
+            };
+            Value::Int(c as i64)
+        }
+
+        // --------------------------
+        // chr(n) -> символ с кодом n
+        // --------------------------
+        "chr" => {
+            let n = match args {
+                [Value::Int(n)] => *n,
+                _ => return error("chr(n) expects exactly 1 int argument"),
+            };
+            match u32::try_from(n).ok().and_then(char::from_u32) {
+                Some(c) => Value::Char(c),
+                None => return error(format!("chr(n): {} is not a valid character code", n)),
+            }
+        }
+
+        // --------------------------
+        // round(x) -> ближайшее int (половина — от нуля: 2.5 -> 3)
+        // round(x, digits) -> float, округлённый до digits знаков после точки
+        // --------------------------
+        "round" => match args {
+            [Value::Int(n)] => Value::Int(*n),
+            [Value::Float(x)] => {
+                let r = x.round();
+                if r.is_finite() && r >= i64::MIN as f64 && r < i64::MAX as f64 {
+                    Value::Int(r as i64)
+                } else {
+                    return error(format!("round(x): float {} is out of int range", x));
+                }
+            }
+            [x, Value::Int(digits)] => {
+                let x = match number(x) {
+                    Some(x) => x,
+                    None => {
+                        return error(format!("round(x, digits): x must be a number, got {:?}", x));
+                    }
+                };
+                let scale = 10f64.powi(decimal_digits("round", *digits)?);
+                let scaled = x * scale;
+                // если x * 10^digits не влезает в float, у x и так меньше
+                // digits знаков после точки — округлять нечего
+                if scaled.is_finite() {
+                    Value::Float(scaled.round() / scale)
+                } else {
+                    Value::Float(x)
                 }
-                other => panic!("tail(list): argument must be list, got {:?}", other),
             }
+            _ => return error("round(x) / round(x, digits) expects a number and an optional int"),
+        },
+
+        // --------------------------
+        // format_float(x, digits) -> строка с ровно digits знаками после точки
+        // --------------------------
+        "format_float" => match args {
+            [x, Value::Int(digits)] => {
+                let x = match number(x) {
+                    Some(x) => x,
+                    None => {
+                        return error(format!(
+                            "format_float(x, digits): x must be a number, got {:?}",
+                            x
+                        ));
+                    }
+                };
+                let digits = decimal_digits("format_float", *digits)? as usize;
+                Value::Str(format!("{:.*}", digits, x))
+            }
+            _ => return error("format_float(x, digits) expects a number and an int"),
+        },
+
+        // --------------------------
+        // pad_left(s, width)  -> "  s": дополнить пробелами слева до width символов
+        // pad_right(s, width) -> "s  ": то же справа
+        // center(s, width)    -> " s  ": поровну с обеих сторон (лишний — справа)
+        // Третий аргумент — символ-заполнитель: pad_left("7", 3, '0') -> "007".
+        // Ширина считается в символах, а не в байтах; длинная строка не обрезается.
+        // --------------------------
+        "pad_left" | "pad_right" | "center" => {
+            let (text, width, fill) = match args {
+                [Value::Str(text), Value::Int(width)] => (text, *width, ' '),
+                [Value::Str(text), Value::Int(width), Value::Char(fill)] => (text, *width, *fill),
+                [Value::Str(text), Value::Int(width), Value::Str(fill)]
+                    if fill.chars().count() == 1 =>
+                {
+                    (text, *width, fill.chars().next().unwrap())
+                }
+                _ => {
+                    return error(format!(
+                        "{}(s, width, fill?) expects a str, an int and an optional char",
+                        name
+                    ));
+                }
+            };
+            if width < 0 {
+                return error(format!("{}(s, width): width must not be negative", name));
+            }
+            let missing = (width as usize).saturating_sub(text.chars().count());
+            let (left, right) = match name {
+                "pad_left" => (missing, 0),
+                "pad_right" => (0, missing),
+                _ => (missing / 2, missing - missing / 2),
+            };
+            let fill = |count: usize| std::iter::repeat_n(fill, count).collect::<String>();
+            Value::Str(format!("{}{}{}", fill(left), text, fill(right)))
+        }
+
+        // --------------------------
+        // join_lines(lines) -> строки через "\n" (обратное к read_lines)
+        //   join_lines(["a", "b"]) -> "a\nb"
+        // --------------------------
+        "join_lines" => {
+            let lines = match args {
+                [Value::List(lines)] => lines,
+                _ => return error("join_lines(lines) expects exactly 1 list argument"),
+            };
+            let mut parts = Vec::with_capacity(lines.len());
+            for line in lines.iter() {
+                match line {
+                    Value::Str(line) => parts.push(line.as_str()),
+                    other => {
+                        return error(format!(
+                            "join_lines(lines): lines must be str, got {:?}",
+                            other
+                        ));
+                    }
+                }
+            }
+            Value::Str(parts.join("\n"))
         }
 
+        // --------------------------
+        // build_string(pieces) -> все строки (и символы) списка подряд, одной строкой.
+        // Память под результат выделяется один раз, поэтому собирать большую
+        // строку так быстрее, чем `s = s + piece` в цикле:
+        //
+        //   var mut parts = []
+        //   for i in 1000 { parts = push(parts, str(i)) }
+        //   var s = build_string(parts)
+        // --------------------------
+        "build_string" => {
+            let pieces = match args {
+                [Value::List(pieces)] => pieces,
+                _ => return error("build_string(pieces) expects exactly 1 list argument"),
+            };
+            let mut len = 0;
+            for piece in pieces.iter() {
+                len += match piece {
+                    Value::Str(s) => s.len(),
+                    Value::Char(c) => c.len_utf8(),
+                    other => {
+                        return error(format!(
+                            "build_string(pieces): pieces must be str or char, got {}",
+                            describe_value(other)
+                        ));
+                    }
+                };
+            }
+            let mut result = String::with_capacity(len);
+            for piece in pieces.iter() {
+                match piece {
+                    Value::Str(s) => result.push_str(s),
+                    Value::Char(c) => result.push(*c),
+                    _ => unreachable!("checked above"),
+                }
+            }
+            Value::Str(result)
+        }
+
+        // --------------------------
+        // format_table(rows) -> таблица текстом: строки через "\n",
+        // колонки выровнены по самой широкой ячейке и разделены двумя пробелами.
+        // Ячейки печатаются как в print; строки могут быть разной длины.
+        //   format_table([["a", 1], ["bcd", 22]]) -> "a    1\nbcd  22"
+        // --------------------------
+        "format_table" => {
+            let rows = match args {
+                [Value::List(rows)] => rows,
+                _ => return error("format_table(rows) expects exactly 1 list argument"),
+            };
+            let mut cells: Vec<Vec<String>> = Vec::with_capacity(rows.len());
+            for row in rows.iter() {
+                match row {
+                    Value::List(row) | Value::Tuple(row) => {
+                        cells.push(row.iter().map(format_value).collect())
+                    }
+                    other => {
+                        return error(format!(
+                            "format_table(rows): each row must be a list, got {:?}",
+                            other
+                        ));
+                    }
+                }
+            }
+            let mut widths: Vec<usize> = Vec::new();
+            for row in &cells {
+                for (i, cell) in row.iter().enumerate() {
+                    let width = cell.chars().count();
+                    match widths.get_mut(i) {
+                        Some(w) => *w = (*w).max(width),
+                        None => widths.push(width),
+                    }
+                }
+            }
+            let lines: Vec<String> = cells
+                .iter()
+                .map(|row| {
+                    let mut line = String::new();
+                    for (i, cell) in row.iter().enumerate() {
+                        if i + 1 < row.len() {
+                            let pad = widths[i] - cell.chars().count();
+                            line.push_str(cell);
+                            line.extend(std::iter::repeat_n(' ', pad + 2));
+                        } else {
+                            // у последней ячейки хвостовых пробелов нет
+                            line.push_str(cell);
+                        }
+                    }
+                    line
+                })
+                .collect();
+            Value::Str(lines.join("\n"))
+        }
+
+        // --------------------------
+        // abs(x) -> модуль числа; abs от минимального int — ошибка переполнения
+        // --------------------------
+        "abs" => match args {
+            [Value::Int(n)] => match n.checked_abs() {
+                Some(n) => Value::Int(n),
+                None => return error("integer overflow in 'abs'"),
+            },
+            [Value::Float(x)] => Value::Float(x.abs()),
+            _ => return error("abs(x) expects exactly 1 int or float argument"),
+        },
+
+        // --------------------------
+        // abs_diff(a, b) -> |a - b| для int. Разность считается без переполнения,
+        // ошибка — только если сам результат не помещается в int.
+        // --------------------------
+        "abs_diff" => match args {
+            [Value::Int(a), Value::Int(b)] => match i64::try_from(a.abs_diff(*b)) {
+                Ok(diff) => Value::Int(diff),
+                Err(_) => return error("integer overflow in 'abs_diff'"),
+            },
+            _ => return error("abs_diff(a, b) expects exactly 2 int arguments"),
+        },
+
+        // --------------------------
+        // divmod(a, b) -> (a / b, остаток) для int — кортеж, который удобно
+        // разобрать: `var q, r = divmod(17, 5)`. Частное округляется к нулю,
+        // как у `/`, поэтому всегда a == q * b + r, а знак r — как у a.
+        // --------------------------
+        "divmod" => match args {
+            [Value::Int(_), Value::Int(0)] => return error("division by zero"),
+            [Value::Int(a), Value::Int(b)] => match (a.checked_div(*b), a.checked_rem(*b)) {
+                (Some(q), Some(r)) => Value::Tuple(Rc::new(vec![Value::Int(q), Value::Int(r)])),
+                _ => return error("integer overflow in 'divmod'"),
+            },
+            _ => return error("divmod(a, b) expects exactly 2 int arguments"),
+        },
+
+        // --------------------------
+        // add_sat(a, b) / sub_sat(a, b) -> a + b / a - b для int, но вместо
+        // переполнения результат упирается в минимальный / максимальный int.
+        // --------------------------
+        "add_sat" => match args {
+            [Value::Int(a), Value::Int(b)] => Value::Int(a.saturating_add(*b)),
+            _ => return error("add_sat(a, b) expects exactly 2 int arguments"),
+        },
+        "sub_sat" => match args {
+            [Value::Int(a), Value::Int(b)] => Value::Int(a.saturating_sub(*b)),
+            _ => return error("sub_sat(a, b) expects exactly 2 int arguments"),
+        },
+
+        // --------------------------
+        // clamp(x, lo, hi)
+        // Ограничивает x отрезком [lo, hi]: lo, если x < lo; hi, если x > hi.
+        // Для int — int; если хоть один аргумент float — float.
+        // --------------------------
+        "clamp" => match args {
+            [Value::Int(x), Value::Int(lo), Value::Int(hi)] => {
+                if lo > hi {
+                    return error(format!(
+                        "clamp(x, lo, hi): lo {} is greater than hi {}",
+                        lo, hi
+                    ));
+                }
+                Value::Int(*x.clamp(lo, hi))
+            }
+            [x, lo, hi] => {
+                let (x, lo, hi) = match (number(x), number(lo), number(hi)) {
+                    (Some(x), Some(lo), Some(hi)) => (x, lo, hi),
+                    _ => return error("clamp(x, lo, hi): arguments must be int or float"),
+                };
+                // NaN в границах тоже считаем ошибкой
+                if lo.is_nan() || hi.is_nan() || lo > hi {
+                    return error(format!(
+                        "clamp(x, lo, hi): lo {} is greater than hi {}",
+                        lo, hi
+                    ));
+                }
+                Value::Float(x.clamp(lo, hi))
+            }
+            _ => return error("clamp(x, lo, hi) expects exactly 3 arguments"),
+        },
+
+        // --------------------------
+        // argmax(list) / argmin(list) -> индекс наибольшего / наименьшего элемента
+        // (при равенстве — первого). Элементы — числа или строки, как ключи
+        // max_by / min_by; пустой список — ошибка.
+        //
+        //   argmax([3, 9, 2]) -> 1
+        // --------------------------
+        "argmax" | "argmin" => {
+            let items = match args {
+                [Value::List(items)] => items,
+                _ => return error(format!("{}(list) expects exactly 1 list argument", name)),
+            };
+            let wanted = if name == "argmax" {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+            let mut best: Option<(usize, &Value)> = None;
+            for (i, item) in items.iter().enumerate() {
+                let better = match best {
+                    None => true,
+                    Some((_, best_item)) => match compare_keys(item, best_item) {
+                        Some(ord) => ord == wanted,
+                        None => {
+                            return error(format!(
+                                "{}(list): cannot compare {} and {}",
+                                name,
+                                describe_value(item),
+                                describe_value(best_item)
+                            ));
+                        }
+                    },
+                };
+                if better {
+                    best = Some((i, item));
+                }
+            }
+            match best {
+                Some((i, _)) => Value::Int(i as i64),
+                None => return error(format!("{}(list): list is empty", name)),
+            }
+        }
+
+        // --------------------------
+        // all(list) -> все элементы true (для пустого списка — true)
+        // any(list) -> хотя бы один true (для пустого списка — false)
+        // Элементы должны быть bool.
+        // --------------------------
+        "all" | "any" => {
+            let items = match args {
+                [Value::List(items)] => items,
+                _ => return error(format!("{}(list) expects exactly 1 list argument", name)),
+            };
+            let mut flags = Vec::with_capacity(items.len());
+            for item in items.iter() {
+                match item {
+                    Value::Bool(b) => flags.push(*b),
+                    other => {
+                        return error(format!(
+                            "{}(list): elements must be bool, got {:?}",
+                            name, other
+                        ));
+                    }
+                }
+            }
+            if name == "all" {
+                Value::Bool(flags.iter().all(|&b| b))
+            } else {
+                Value::Bool(flags.iter().any(|&b| b))
+            }
+        }
+
+        // --------------------------
+        // first(list, default) / last(list, default)
+        // Первый / последний элемент списка, а для пустого — default.
+        // В отличие от head, никогда не падают на пустом списке.
+        // --------------------------
+        "first" | "last" => {
+            let (items, default) = match args {
+                [Value::List(items), default] => (items, default),
+                [other, _] => {
+                    return error(format!(
+                        "{}(list, default): first arg must be list, got {:?}",
+                        name, other
+                    ));
+                }
+                _ => {
+                    return error(format!(
+                        "{}(list, default) expects exactly 2 arguments",
+                        name
+                    ));
+                }
+            };
+            let item = if name == "first" {
+                items.first()
+            } else {
+                items.last()
+            };
+            item.unwrap_or(default).clone()
+        }
+
+        // --------------------------
+        // copy(x) -> независимая глубокая копия списка / словаря / кортежа
+        // (см. deep_copy)
+        // --------------------------
+        "copy" => match args {
+            [value] => deep_copy(value)?,
+            _ => return error("copy(x) expects exactly 1 argument"),
+        },
+
+        // --------------------------
+        // bool(x) -> истинность значения (см. truthy):
+        //   bool(0) == false, bool("a") == true, bool([]) == false
+        // --------------------------
+        "bool" => match args {
+            [value] => Value::Bool(truthy(value)),
+            _ => return error("bool(x) expects exactly 1 argument"),
+        },
+
         // --------------------------
         // str(x)
         // Преобразование к строке:
         //   int  -> "123"
+        //   float -> "1.5", "3.0"
         //   bool -> "true"/"false"
         //   str  -> как есть
         //   list -> строка вида "[1, 2, 3]" (упрощённо)
         // --------------------------
         "str" => {
             if args.len() != 1 {
-                panic!("str(x) expects exactly 1 argument");
+                return error("str(x) expects exactly 1 argument");
             }
             let s = match &args[0] {
                 Value::Int(n) => n.to_string(),
+                Value::Float(x) => float_to_string(*x),
                 Value::Bool(b) => b.to_string(),
                 Value::Str(s) => s.clone(),
+                Value::Char(c) => c.to_string(),
                 Value::List(items) => {
                     // Простое представление списка
                     let mut parts = Vec::new();
-                    for it in items {
+                    for it in items.iter() {
                         parts.push(format!("{:?}", it));
                     }
                     format!("[{}]", parts.join(", "))
                 }
+                Value::Dict(_) | Value::Tuple(_) | Value::Func(_) => format_value(&args[0]),
                 Value::Unit => "()".to_string(),
             };
-            Some(Value::Str(s))
+            Value::Str(s)
         }
 
         // --------------------------
         // int(x)
         // Преобразование к целому:
-        //   int  -> int
-        //   bool -> 0/1
-        //   str  -> parse::<i64>()
+        //   int   -> int
+        //   float -> без дробной части (3.9 -> 3)
+        //   bool  -> 0/1
+        //   str   -> parse::<i64>()
         // --------------------------
         "int" => {
             if args.len() != 1 {
-                panic!("int(x) expects exactly 1 argument");
+                return error("int(x) expects exactly 1 argument");
             }
-            let n = match &args[0] {
-                Value::Int(n) => *n,
-                Value::Bool(b) => {
-                    if *b {
-                        1
-                    } else {
-                        0
-                    }
-                }
-                Value::Str(s) => s.parse::<i64>().unwrap_or_else(|_| {
-                    panic!("int(x): cannot parse string {:?} as integer", s);
-                }),
-                other => panic!("int(x) is not defined for {:?}", other),
+            Value::Int(to_int(&args[0])?)
+        }
+
+        // --------------------------
+        // float(x)
+        // Преобразование к float:
+        //   float -> float
+        //   int   -> float
+        //   str   -> парсинг ("2.5", "3")
+        // --------------------------
+        "float" => {
+            if args.len() != 1 {
+                return error("float(x) expects exactly 1 argument");
+            }
+            Value::Float(to_float(&args[0])?)
+        }
+
+        // --------------------------
+        // int_or(x, default) / float_or(x, default)
+        // Как int(x) / float(x), но вместо ошибки разбора возвращают default:
+        //   int_or("42", 0)   -> 42
+        //   int_or("oops", -1) -> -1
+        // --------------------------
+        "int_or" => {
+            if args.len() != 2 {
+                return error("int_or(x, default) expects exactly 2 arguments");
+            }
+            match to_int(&args[0]) {
+                Ok(n) => Value::Int(n),
+                Err(_) => args[1].clone(),
+            }
+        }
+        "float_or" => {
+            if args.len() != 2 {
+                return error("float_or(x, default) expects exactly 2 arguments");
+            }
+            match to_float(&args[0]) {
+                Ok(x) => Value::Float(x),
+                Err(_) => args[1].clone(),
+            }
+        }
+
+        // --------------------------
+        // parse_int(s) / parse_float(s) -> [ok, value]
+        // Разбор строки без ошибки: [true, 42] при успехе,
+        // [false, 0] (или [false, 0.0]) — если строка не число.
+        // --------------------------
+        "parse_int" => match args {
+            [s @ Value::Str(_)] => match to_int(s) {
+                Ok(n) => Value::list(vec![Value::Bool(true), Value::Int(n)]),
+                Err(_) => Value::list(vec![Value::Bool(false), Value::Int(0)]),
+            },
+            _ => return error("parse_int(s) expects exactly 1 str argument"),
+        },
+        "parse_float" => match args {
+            [s @ Value::Str(_)] => match to_float(s) {
+                Ok(x) => Value::list(vec![Value::Bool(true), Value::Float(x)]),
+                Err(_) => Value::list(vec![Value::Bool(false), Value::Float(0.0)]),
+            },
+            _ => return error("parse_float(s) expects exactly 1 str argument"),
+        },
+
+        // --------------------------
+        // exit() / exit(code)
+        // Завершает программу с кодом возврата (по умолчанию 0).
+        // Не вызывает process::exit сам: сигнал Exit раскручивает
+        // интерпретатор, а выходит из процесса уже main.rs.
+        // --------------------------
+        "exit" => {
+            let code = match args {
+                [] => 0,
+                [Value::Int(code)] => match i32::try_from(*code) {
+                    Ok(code) => code,
+                    Err(_) => return error(format!("exit(code): code {} is out of range", code)),
+                },
+                [other] => return error(format!("exit(code): code must be int, got {:?}", other)),
+                _ => return error("exit(code) expects at most 1 argument"),
             };
-            Some(Value::Int(n))
+            return Err(RuntimeError::Exit(code));
+        }
+
+        // --------------------------
+        // raise(message)
+        // Вызывает ошибку исполнения с этим текстом; её можно поймать в `try`.
+        // --------------------------
+        "raise" => match args {
+            [message] => return error(format_value(message)),
+            _ => return error("raise(message) expects exactly 1 argument"),
+        },
+
+        // --------------------------
+        // assert_eq(a, b) / assert_eq(a, b, message)
+        // Ничего не делает, если a == b (то же равенство, что у `==`);
+        // иначе — ошибка, в которой видны обе стороны:
+        //   assertion failed: left = [1, 2], right = [1, 3]
+        // --------------------------
+        "assert_eq" => {
+            let (left, right, message) = match args {
+                [left, right] => (left, right, None),
+                [left, right, message] => (left, right, Some(format_value(message))),
+                _ => return error("assert_eq(a, b, message?) expects 2 or 3 arguments"),
+            };
+            if left != right {
+                let sides = format!("left = {}, right = {}", repr_value(left), repr_value(right));
+                return match message {
+                    Some(message) => error(format!("assertion failed: {}: {}", message, sides)),
+                    None => error(format!("assertion failed: {}", sides)),
+                };
+            }
+            Value::Unit
         }
 
         // неизвестная функция — пусть ищет пользовательскую
+        _ => return Ok(None),
+    };
+    Ok(Some(result))
+}
+
+/// Глубокая копия: списки, кортежи и словари на любой глубине
+/// пересобираются заново и больше не делят `Rc` с оригиналом.
+/// Остальные значения просто клонируются (функции — по ссылке).
+fn deep_copy(value: &Value) -> Result<Value, RuntimeError> {
+    Ok(match value {
+        Value::List(items) => Value::list(items.iter().map(deep_copy).collect::<Result<_, _>>()?),
+        Value::Tuple(items) => Value::Tuple(Rc::new(
+            items.iter().map(deep_copy).collect::<Result<_, _>>()?,
+        )),
+        Value::Dict(dict) => {
+            let mut copy = Dict::new();
+            for (key, value) in dict.iter() {
+                copy.insert(deep_copy(key)?, deep_copy(value)?)?;
+            }
+            Value::Dict(Rc::new(copy))
+        }
+        other => other.clone(),
+    })
+}
+
+/// Число как `f64` (int или float), иначе `None`.
+fn number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Int(n) => Some(*n as f64),
+        Value::Float(x) => Some(*x),
         _ => None,
     }
 }
 
+/// Сравнение ключей для `max_by` / `min_by` / `sort_by` и элементов для
+/// `argmax` / `argmin`: числа (int и float вперемешку) сравниваются по значению,
+/// строки и символы — лексикографически. `None` — значения несравнимы.
+pub fn compare_keys(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Str(a), Value::Str(b)) => Some(a.cmp(b)),
+        (Value::Char(a), Value::Char(b)) => Some(a.cmp(b)),
+        _ => number(a)?.partial_cmp(&number(b)?),
+    }
+}
+
+/// Число знаков после точки для `round` / `format_float`: от 0 до 300.
+fn decimal_digits(name: &str, digits: i64) -> Result<i32, RuntimeError> {
+    match i32::try_from(digits) {
+        Ok(d) if (0..=300).contains(&d) => Ok(d),
+        _ if digits < 0 => error(format!(
+            "{}: digits must not be negative, got {}",
+            name, digits
+        )),
+        _ => error(format!("{}: too many digits ({})", name, digits)),
+    }
+}
+
+/// Строгое преобразование к int (для `int`, `int_or`).
+/// float отбрасывает дробную часть; NaN и слишком большие значения — ошибка.
+fn to_int(v: &Value) -> Result<i64, RuntimeError> {
+    match v {
+        Value::Int(n) => Ok(*n),
+        Value::Float(x) => {
+            if x.is_finite() && *x >= i64::MIN as f64 && *x < i64::MAX as f64 {
+                Ok(x.trunc() as i64)
+            } else {
+                error(format!("int(x): float {} is out of int range", x))
+            }
+        }
+        Value::Bool(b) => Ok(if *b { 1 } else { 0 }),
+        Value::Str(s) => match s.parse::<i64>() {
+            Ok(n) => Ok(n),
+            // число записано верно, но не помещается в i64
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    IntErrorKind::PosOverflow | IntErrorKind::NegOverflow
+                ) =>
+            {
+                error(format!("int(x): integer out of range: {:?}", s))
+            }
+            Err(_) => error(format!("int(x): cannot parse string {:?} as integer", s)),
+        },
+        other => error(format!("int(x) is not defined for {:?}", other)),
+    }
+}
+
+/// Строгое преобразование к float (для `float`, `float_or`).
+fn to_float(v: &Value) -> Result<f64, RuntimeError> {
+    match v {
+        Value::Float(x) => Ok(*x),
+        Value::Int(n) => Ok(*n as f64),
+        Value::Str(s) => match s.parse::<f64>() {
+            Ok(x) => Ok(x),
+            Err(_) => error(format!("float(x): cannot parse string {:?} as float", s)),
+        },
+        other => error(format!("float(x) is not defined for {:?}", other)),
+    }
+}
+
+/// Проверяет аргументы `range(n)` и возвращает `n`.
+/// Общая проверка для builtin-а и для `for i in range(n)`, который
+/// интерпретатор обходит без создания списка.
+pub fn range_len(args: &[Value]) -> Result<i64, RuntimeError> {
+    if args.len() != 1 {
+        return error("range(n) expects exactly 1 argument");
+    }
+    let n = match args[0] {
+        Value::Int(n) => n,
+        ref other => return error(format!("range(n): n must be int, got {:?}", other)),
+    };
+    if n < 0 {
+        return error("range(n): n must be >= 0");
+    }
+    Ok(n)
+}
+
+/// Переводит индекс языка (может быть отрицательным — отсчёт с конца)
+/// в индекс Rust. `None`, если индекс вне `0..len`.
+pub fn resolve_index(i: i64, len: usize) -> Option<usize> {
+    let i = if i < 0 { i + len as i64 } else { i };
+    usize::try_from(i).ok().filter(|&i| i < len)
+}
+
 /// Внутренний helper для print: красиво печатает любое Value.
-fn print_value(v: &Value) {
+/// Печатает значения через `sep` и дописывает `end`.
+fn print_values(
+    out: &mut dyn Write,
+    values: &[Value],
+    sep: &str,
+    end: &str,
+) -> Result<(), RuntimeError> {
+    let mut text = String::new();
+    for (i, v) in values.iter().enumerate() {
+        if i > 0 {
+            text.push_str(sep);
+        }
+        text.push_str(&format_value(v));
+    }
+    text.push_str(end);
+
+    match out.write_all(text.as_bytes()) {
+        Ok(()) => Ok(()),
+        Err(e) => error(format!("failed to write output: {}", e)),
+    }
+}
+
+/// Текстовое представление значения для вывода.
+fn format_value(v: &Value) -> String {
     match v {
-        Value::Int(n) => print!("{n}"),
-        Value::Bool(b) => print!("{b}"),
-        Value::Str(s) => print!("{s}"),
-        Value::Unit => print!("()"),
+        Value::Int(n) => n.to_string(),
+        Value::Float(x) => float_to_string(*x),
+        Value::Bool(b) => b.to_string(),
+        Value::Str(s) => s.clone(),
+        Value::Char(c) => c.to_string(),
+        Value::Unit => "()".to_string(),
+        Value::Func(closure) => format!("<fn {}>", closure.func.name),
 
         Value::List(items) => {
-            print!("[");
-            let mut first = true;
-            for item in items {
-                if !first {
-                    print!(", ");
-                }
-                first = false;
-                match item {
-                    Value::Int(n) => print!("{n}"),
-                    Value::Bool(b) => print!("{b}"),
-                    Value::Str(s) => print!("\"{s}\""),
-                    Value::Unit => print!("()"),
-                    // Вложенные списки/сложные значения пока просто через Debug
-                    Value::List(_) => print!("{:?}", item),
-                }
+            let parts: Vec<String> = items.iter().map(repr_value).collect();
+            format!("[{}]", parts.join(", "))
+        }
+        Value::Dict(dict) => {
+            let parts: Vec<String> = dict
+                .iter()
+                .map(|(key, value)| format!("{}: {}", repr_value(key), repr_value(value)))
+                .collect();
+            format!("{{{}}}", parts.join(", "))
+        }
+        Value::Tuple(items) => {
+            let parts: Vec<String> = items.iter().map(repr_value).collect();
+            // `(1,)`, чтобы кортеж из одного элемента не выглядел как число в скобках
+            match parts.as_slice() {
+                [single] => format!("({},)", single),
+                _ => format!("({})", parts.join(", ")),
             }
-            print!("]");
         }
     }
 }
+
+/// float печатается всегда с точкой: `3.0`, а не `3`.
+fn float_to_string(x: f64) -> String {
+    if x.is_finite() && x.fract() == 0.0 {
+        format!("{:.1}", x)
+    } else {
+        x.to_string()
+    }
+}
+
+/// Истинность значения для `bool(x)` (и условий при `--truthy`):
+/// ложны `false`, `0`, `0.0`, пустые строка / список / словарь / кортеж
+/// и `()`, всё остальное (в том числе любой символ и функция) — истинно.
+pub fn truthy(v: &Value) -> bool {
+    match v {
+        Value::Bool(b) => *b,
+        Value::Int(n) => *n != 0,
+        Value::Float(x) => *x != 0.0,
+        Value::Str(s) => !s.is_empty(),
+        Value::List(items) => !items.is_empty(),
+        Value::Dict(dict) => dict.len() != 0,
+        Value::Tuple(items) => !items.is_empty(),
+        Value::Char(_) | Value::Func(_) => true,
+        Value::Unit => false,
+    }
+}
+
+/// Значение вместе с именем его типа — для сообщений об ошибках:
+/// `int 3`, `str "x"`, `list [1, 2]`.
+pub fn describe_value(v: &Value) -> String {
+    let type_name = match v {
+        Value::Int(_) => "int",
+        Value::Float(_) => "float",
+        Value::Bool(_) => "bool",
+        Value::Str(_) => "str",
+        Value::Char(_) => "char",
+        Value::List(_) => "list",
+        Value::Dict(_) => "dict",
+        Value::Tuple(_) => "tuple",
+        Value::Func(_) => "func",
+        Value::Unit => "unit",
+    };
+    format!("{} {}", type_name, repr_value(v))
+}
+
+/// Представление значения внутри списка/словаря: строки — в кавычках.
+fn repr_value(v: &Value) -> String {
+    match v {
+        Value::Str(s) => format!("\"{s}\""),
+        Value::Char(c) => format!("'{c}'"),
+        other => format_value(other),
+    }
+}